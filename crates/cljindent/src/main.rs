@@ -1,25 +1,91 @@
 use std::{fs::{read_to_string, OpenOptions}, path::Path, io::Write};
 use walkdir::WalkDir;
-use formatter;
 use similar::TextDiff;
+use formatter::Config;
+use tokenizer::Tokenizer;
+use tokenizer::dialect::Dialect;
+use tokenizer::tree::{self, TokenTree};
 
-fn format(file: &Path) {
+/// Prints syntax and delimiter errors of the file. Returns true if there are any
+fn report_errors(file: &Path, data: &str, dialect: Dialect) -> bool {
+    let mut found = false;
+    let tokens: Vec<_> = Tokenizer::from(data).with_dialect(dialect).collect();
+
+    for token in &tokens {
+        if let Some(error) = token.error() {
+            let start = token.start();
+            eprintln!("{}:{}:{}: {}", file.display(), start.line, start.column, error);
+            found = true;
+        }
+    }
+
+    for error in tree::errors(&TokenTree::build(tokens)) {
+        let start = error.position();
+        eprintln!("{}:{}:{}: {}", file.display(), start.line, start.column, error);
+        found = true;
+    }
+
+    found
+}
+
+/// Reads `settings.json` of the directory, if any. Unknown keys are reported and ignored
+fn load_config(dir: &str) -> Config {
+    let path = Path::new(dir).join("settings.json");
+    let Ok(json) = read_to_string(&path) else {
+        return Config::default();
+    };
+
+    match Config::from_json(&json) {
+        Ok((config, unknown)) => {
+            for key in unknown {
+                eprintln!("{}: unknown key `{}`", path.display(), key);
+            }
+            config
+        },
+        Err(error) => {
+            eprintln!("{}: {}", path.display(), error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints the errors of the file and that it is skipped. Returns true if it is
+fn skip(file: &Path, data: &str, dialect: Dialect) -> bool {
+    if !report_errors(file, data, dialect) {
+        return false;
+    }
+    eprintln!("Skip {}", file.display());
+    true
+}
+
+/// Returns false if the file is skipped because of errors
+fn format(file: &Path, dialect: Dialect, config: &Config) -> bool {
     let data = read_to_string(file).unwrap();
-    let formatted = formatter::xformat(&data);
+    if skip(file, &data, dialect) {
+        return false;
+    }
+
+    let formatted = formatter::format_with_config(&data, dialect, config);
     
     if data == formatted {
-        return;
+        return true;
     }
     
     let mut out = OpenOptions::new().write(true).truncate(true).open(file).unwrap();
-    out.write(formatted.as_bytes()).unwrap();
+    out.write_all(formatted.as_bytes()).unwrap();
 
     println!("Format {}", file.display());
+    true
 }
 
-fn diff(file: &Path) {
+/// Returns false if the file is skipped because of errors
+fn diff(file: &Path, dialect: Dialect, config: &Config) -> bool {
     let data = read_to_string(file).unwrap();
-    let formatted = formatter::xformat(&data);
+    if skip(file, &data, dialect) {
+        return false;
+    }
+
+    let formatted = formatter::format_with_config(&data, dialect, config);
     let diff = TextDiff::from_lines(&data, &formatted);
     print!(
         "{}",
@@ -28,15 +94,30 @@ fn diff(file: &Path) {
             .context_radius(3)
             .header(&file.display().to_string(), &file.display().to_string())
     );
+    true
 }
 
-fn run(dir: &str, action: fn(&Path)) {
+/// Applies the action to the Clojure files of the directory and exits with an error
+/// if any file is skipped
+fn run(dir: &str, action: fn(&Path, Dialect, &Config) -> bool) {
+    let config = load_config(dir);
+    let mut skipped = 0;
     WalkDir::new(dir)
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
-        .filter(|e| e.file_name().to_string_lossy().ends_with(".clj"))
-        .for_each(|x| action(x.path()));
+        .for_each(|x| {
+            if let Some(dialect) = Dialect::from_path(x.path()) {
+                if !action(x.path(), dialect, &config) {
+                    skipped += 1;
+                }
+            }
+        });
+
+    if skipped > 0 {
+        eprintln!("{} file(s) skipped because of errors", skipped);
+        std::process::exit(1);
+    }
 }
 
 fn main() {
@@ -0,0 +1,157 @@
+//! Lossless concrete syntax tree of Clojure source.
+//!
+//! Every token, including whitespace and comments, is kept in the tree,
+//! so `parse(source).to_string() == source` for any input, even a malformed one.
+
+mod node;
+mod parser;
+pub mod pretty;
+
+pub use node::{Form, Node, Prefixed, Seq, SyntaxTree};
+pub use parser::MAX_DEPTH;
+
+use parser::Parser;
+use tokenizer::Tokenizer;
+use tokenizer::dialect::Dialect;
+
+pub fn parse(source: &str) -> SyntaxTree<'_> {
+    parse_with_dialect(source, Dialect::default())
+}
+
+/// Parses the source read in the given dialect
+pub fn parse_with_dialect(source: &str, dialect: Dialect) -> SyntaxTree<'_> {
+    Parser::new(Tokenizer::from(source).with_dialect(dialect)).parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Form, MAX_DEPTH};
+    use crate::pretty::pretty;
+    use tokenizer::tokenize;
+
+    /// The tree as an S-expression of node kinds, without trivia
+    fn shape(form: &Form) -> String {
+        let children = || form.children().iter().map(|node| shape(&node.form)).collect::<Vec<_>>().join(" ");
+        match form {
+            Form::Atom(token) => token.value().to_string(),
+            Form::Unmatched(token) => format!("(unmatched {})", token.value()),
+            Form::TooDeep(tokens) => format!("(too-deep {})", tokens.len()),
+            Form::List(seq) if seq.close.is_none() => format!("(unclosed {})", children()),
+            Form::List(_) => format!("(list {})", children()),
+            Form::Vector(_) => format!("(vector {})", children()),
+            Form::Map(_) => format!("(map {})", children()),
+            Form::Set(_) => format!("(set {})", children()),
+            Form::Fn(_) => format!("(fn {})", children()),
+            Form::ReaderConditional(_) => format!("(reader-conditional {})", children()),
+            Form::Metadata { .. } => format!("(meta {})", children()),
+            Form::Quote(prefixed) => format!("({} {})", prefixed.prefix.value(), children()),
+            Form::Tagged(prefixed) => format!("({} {})", prefixed.prefix.value(), children()),
+            Form::Discard(_) => format!("(discard {})", children()),
+            Form::NamespacedMap(prefixed) => format!("({} {})", prefixed.prefix.value(), children()),
+            Form::Dispatch(_) => format!("(dispatch {})", children()),
+        }
+    }
+
+    fn shapes(source: &str) -> Vec<String> {
+        parse(source).forms.iter().map(|node| shape(&node.form)).collect()
+    }
+
+    #[test]
+    fn forms() {
+        assert_eq!(shapes("(a [b] {:c d} #{e} #(f %))"), vec!["(list a (vector b) (map :c d) (set e) (fn f %))"]);
+        assert_eq!(shapes("#?(:clj 1) #?@(:cljs [2])"), vec![
+            "(reader-conditional (list :clj 1))",
+            "(reader-conditional (list :cljs (vector 2)))",
+        ]);
+        assert_eq!(shapes("^:private ^{:doc \"x\"} (def a)"), vec![
+            "(meta :private (meta (map :doc \"x\") (list def a)))",
+        ]);
+        assert_eq!(shapes("'a `(b ~c ~@d) @e #'f"), vec!["(' a)", "(` (list b (~ c) (~@ d)))", "(@ e)", "(#' f)"]);
+        assert_eq!(shapes("#inst \"2024\" #_ #_ a b c"), vec!["(#inst \"2024\")", "(discard (discard a))", "b", "c"]);
+        assert_eq!(shapes("#:a{:b 1} #^:k x"), vec!["(#:a (map :b 1))", "(dispatch (meta :k x))"]);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(shapes("(a (b"), vec!["(unclosed a (unclosed b))"]);
+        assert_eq!(shapes(") a"), vec!["(unmatched ))", "a"]);
+        assert_eq!(shapes("(' )"), vec!["(list (' ))"]);
+        assert_eq!(shapes("(^)"), vec!["(list (meta ))"]);
+
+        let tree = parse("(a]");
+        assert!(tree.forms[0].form.seq().unwrap().is_mismatched());
+        assert!(!parse("#{a}").forms[0].form.seq().unwrap().is_mismatched());
+    }
+
+    #[test]
+    fn trivia() {
+        let tree = parse("\u{feff}#!bb\n;; c\n(a ; b\n )\n");
+        assert_eq!(tree.forms[0].trivia.iter().map(|token| token.value()).collect::<Vec<_>>(), vec!["\u{feff}", "#!bb", "\n", ";; c\n"]);
+        let seq = tree.forms[0].form.seq().unwrap();
+        assert_eq!(seq.trivia.iter().map(|token| token.value()).collect::<Vec<_>>(), vec![" ", "; b\n", " "]);
+        assert_eq!(tree.trivia.len(), 1);
+        assert_eq!(tree.forms[0].form.span().range(), 13..22);
+    }
+
+    #[test]
+    fn lossless() {
+        let sources = [
+            "",
+            "  ",
+            "(ns a\n  (:require [b :as c]))\n\n(defn f\n  \"doc\"\n  [x]\n  ;; comment\n  (c/g x))\n",
+            "^{:a 1}\n#?(:clj #inst \"x\" :cljs #js {}) #_ ' ` @ ~@ ~ #' #:a{} #^x #= (+ 1 2)",
+            "(a] ) ( [ { #{ #( \"unterminated",
+            "#",
+            "(a ^ ' #_",
+        ];
+        for source in sources {
+            let tree = parse(source);
+            assert_eq!(tree.to_string(), source);
+            assert_eq!(tree.tokens(), tokenize(source));
+        }
+    }
+
+    /// The innermost sequence along the first children
+    fn innermost<'t, 'a>(mut form: &'t Form<'a>) -> (usize, &'t Form<'a>) {
+        let mut depth = 0;
+        while let Some(node) = form.children().first().filter(|node| node.form.seq().is_some()) {
+            form = &node.form;
+            depth += 1;
+        }
+        (depth, form)
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let nested = |open: &str, close: &str, depth: usize| format!("{}a{}", open.repeat(depth), close.repeat(depth));
+
+        let source = nested("[", "]", MAX_DEPTH - 1);
+        let tree = parse(&source);
+        let (depth, form) = innermost(&tree.forms[0].form);
+        assert_eq!((depth, shape(form)), (MAX_DEPTH - 2, "(vector a)".to_owned()));
+
+        // The list has `MAX_DEPTH` enclosing vectors
+        let source = format!("{}(a b) c{} d", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        let tree = parse(&source);
+        assert_eq!(tree.forms.len(), 2);
+        let (depth, form) = innermost(&tree.forms[0].form);
+        assert_eq!((depth, shape(form)), (MAX_DEPTH - 1, "(vector (too-deep 5) (too-deep 1))".to_owned()));
+        assert_eq!(shapes(&(nested("'", "", MAX_DEPTH + 2) + " b")).len(), 2);
+
+        let sources = [
+            nested("[", "]", 100_000),
+            nested("(", "", 100_000),
+            nested("{", "]", 100_000),
+            nested("#_ ", "", 100_000),
+            nested("^m ", "", 100_000),
+            nested("#?(", ")", 100_000) + ") b",
+            format!("({} c) d", nested("(' ", ")", 1_000)),
+        ];
+        for source in sources {
+            let tree = parse(&source);
+            assert_eq!(tree.to_string(), source);
+            assert_eq!(tree.tokens(), tokenize(&source));
+            assert!(!pretty(&source, 80).is_empty());
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt;
+
+use tokenizer::span::Span;
+use tokenizer::token::{Token, TokenType};
+
+/// The whole source: top-level forms and the trivia after the last one
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SyntaxTree<'a> {
+    pub forms: Vec<Node<'a>>,
+    /// Whitespace and comments at the end of the source
+    pub trivia: Vec<Token<'a>>,
+}
+
+/// A form with the whitespace, comments, shebang and BOM preceding it
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Node<'a> {
+    pub trivia: Vec<Token<'a>>,
+    pub form: Form<'a>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Form<'a> {
+    /// Symbols, keywords, numbers, strings, characters, regexes, symbolic values
+    /// and error tokens
+    Atom(Token<'a>),
+    List(Seq<'a>),
+    Vector(Seq<'a>),
+    Map(Seq<'a>),
+    Set(Seq<'a>),
+    /// The `#(...)` anonymous function literal
+    Fn(Seq<'a>),
+    /// `#?` or `#?@` followed by a list
+    ReaderConditional(Prefixed<'a>),
+    /// `^meta form`
+    Metadata {
+        marker: Token<'a>,
+        meta: Option<Box<Node<'a>>>,
+        form: Option<Box<Node<'a>>>,
+    },
+    /// `'`, `` ` ``, `~`, `~@`, `@` and `#'` applied to a form
+    Quote(Prefixed<'a>),
+    /// A tagged literal, e.g. `#inst "2024-01-01"`
+    Tagged(Prefixed<'a>),
+    /// `#_` followed by the discarded form
+    Discard(Prefixed<'a>),
+    /// `#:ns` or `#::alias` followed by a map
+    NamespacedMap(Prefixed<'a>),
+    /// Legacy `#^`, `#=` and `#<` dispatch macros applied to the next form
+    Dispatch(Prefixed<'a>),
+    /// A closing delimiter without an opening one
+    Unmatched(Token<'a>),
+    /// A form nested deeper than `MAX_DEPTH`, kept as its tokens, trivia included
+    TooDeep(Vec<Token<'a>>),
+}
+
+/// A delimited sequence of forms
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Seq<'a> {
+    pub open: Token<'a>,
+    pub children: Vec<Node<'a>>,
+    /// Whitespace and comments after the last child
+    pub trivia: Vec<Token<'a>>,
+    /// `None` if the sequence is not closed before the end of the source
+    pub close: Option<Token<'a>>,
+}
+
+/// A prefix applied to the next form
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Prefixed<'a> {
+    pub prefix: Token<'a>,
+    /// `None` if the prefix is followed by a closing delimiter or the end of the source
+    pub form: Option<Box<Node<'a>>>,
+}
+
+impl<'a> Seq<'a> {
+    /// True if closed by a delimiter of another kind, e.g. `(a]`
+    pub fn is_mismatched(&self) -> bool {
+        let expected = match self.open.kind() {
+            TokenType::LParen | TokenType::LAnonFn => TokenType::RParen,
+            TokenType::LBracket => TokenType::RBracket,
+            _ => TokenType::RBrace,
+        };
+        self.close.is_some_and(|close| close.kind() != expected)
+    }
+
+    fn push_tokens(&self, out: &mut Vec<Token<'a>>) {
+        out.push(self.open);
+        for child in &self.children {
+            child.push_tokens(out);
+        }
+        out.extend_from_slice(&self.trivia);
+        out.extend(self.close);
+    }
+
+    fn last_token(&self) -> Token<'a> {
+        self.close
+            .or_else(|| self.children.last().map(|child| child.form.last_token()))
+            .unwrap_or(self.open)
+    }
+}
+
+impl<'a> Prefixed<'a> {
+    fn push_tokens(&self, out: &mut Vec<Token<'a>>) {
+        out.push(self.prefix);
+        if let Some(form) = &self.form {
+            form.push_tokens(out);
+        }
+    }
+
+    fn last_token(&self) -> Token<'a> {
+        self.form.as_ref().map_or(self.prefix, |form| form.form.last_token())
+    }
+}
+
+impl<'a> Form<'a> {
+    /// Nested forms in the source order, e.g. list elements or the quoted form
+    pub fn children(&self) -> Vec<&Node<'a>> {
+        match self {
+            Form::Atom(_) | Form::Unmatched(_) | Form::TooDeep(_) => vec![],
+            Form::List(seq) | Form::Vector(seq) | Form::Map(seq) | Form::Set(seq) | Form::Fn(seq) => {
+                seq.children.iter().collect()
+            },
+            Form::ReaderConditional(prefixed)
+            | Form::Quote(prefixed)
+            | Form::Tagged(prefixed)
+            | Form::Discard(prefixed)
+            | Form::NamespacedMap(prefixed)
+            | Form::Dispatch(prefixed) => prefixed.form.iter().map(|form| form.as_ref()).collect(),
+            Form::Metadata { meta, form, .. } => meta.iter().chain(form).map(|form| form.as_ref()).collect(),
+        }
+    }
+
+    /// The delimited sequence, if the form is one
+    pub fn seq(&self) -> Option<&Seq<'a>> {
+        match self {
+            Form::List(seq) | Form::Vector(seq) | Form::Map(seq) | Form::Set(seq) | Form::Fn(seq) => Some(seq),
+            _ => None,
+        }
+    }
+
+    pub fn first_token(&self) -> Token<'a> {
+        match self {
+            Form::Atom(token) | Form::Unmatched(token) => *token,
+            Form::TooDeep(tokens) => tokens[0],
+            Form::List(seq) | Form::Vector(seq) | Form::Map(seq) | Form::Set(seq) | Form::Fn(seq) => seq.open,
+            Form::ReaderConditional(prefixed)
+            | Form::Quote(prefixed)
+            | Form::Tagged(prefixed)
+            | Form::Discard(prefixed)
+            | Form::NamespacedMap(prefixed)
+            | Form::Dispatch(prefixed) => prefixed.prefix,
+            Form::Metadata { marker, .. } => *marker,
+        }
+    }
+
+    pub fn last_token(&self) -> Token<'a> {
+        match self {
+            Form::Atom(token) | Form::Unmatched(token) => *token,
+            Form::TooDeep(tokens) => tokens[tokens.len() - 1],
+            Form::List(seq) | Form::Vector(seq) | Form::Map(seq) | Form::Set(seq) | Form::Fn(seq) => seq.last_token(),
+            Form::ReaderConditional(prefixed)
+            | Form::Quote(prefixed)
+            | Form::Tagged(prefixed)
+            | Form::Discard(prefixed)
+            | Form::NamespacedMap(prefixed)
+            | Form::Dispatch(prefixed) => prefixed.last_token(),
+            Form::Metadata { marker, meta, form } => form
+                .as_ref()
+                .or(meta.as_ref())
+                .map_or(*marker, |node| node.form.last_token()),
+        }
+    }
+
+    /// The form without the preceding trivia
+    pub fn span(&self) -> Span {
+        Span::new(self.first_token().start(), self.last_token().end())
+    }
+
+    fn push_tokens(&self, out: &mut Vec<Token<'a>>) {
+        match self {
+            Form::Atom(token) | Form::Unmatched(token) => out.push(*token),
+            Form::TooDeep(tokens) => out.extend_from_slice(tokens),
+            Form::List(seq) | Form::Vector(seq) | Form::Map(seq) | Form::Set(seq) | Form::Fn(seq) => seq.push_tokens(out),
+            Form::ReaderConditional(prefixed)
+            | Form::Quote(prefixed)
+            | Form::Tagged(prefixed)
+            | Form::Discard(prefixed)
+            | Form::NamespacedMap(prefixed)
+            | Form::Dispatch(prefixed) => prefixed.push_tokens(out),
+            Form::Metadata { marker, meta, form } => {
+                out.push(*marker);
+                for node in meta.iter().chain(form) {
+                    node.push_tokens(out);
+                }
+            },
+        }
+    }
+}
+
+impl<'a> Node<'a> {
+    fn push_tokens(&self, out: &mut Vec<Token<'a>>) {
+        out.extend_from_slice(&self.trivia);
+        self.form.push_tokens(out);
+    }
+
+    /// All tokens of the node, including the trivia, in the source order
+    pub fn tokens(&self) -> Vec<Token<'a>> {
+        let mut out = Vec::new();
+        self.push_tokens(&mut out);
+        out
+    }
+}
+
+impl<'a> SyntaxTree<'a> {
+    /// All tokens of the source, in the source order
+    pub fn tokens(&self) -> Vec<Token<'a>> {
+        let mut out = Vec::new();
+        for node in &self.forms {
+            node.push_tokens(&mut out);
+        }
+        out.extend_from_slice(&self.trivia);
+        out
+    }
+}
+
+fn write_tokens(f: &mut fmt::Formatter<'_>, tokens: &[Token]) -> fmt::Result {
+    tokens.iter().try_for_each(|token| f.write_str(token.value()))
+}
+
+/// Writes the source back, byte for byte
+impl fmt::Display for SyntaxTree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_tokens(f, &self.tokens())
+    }
+}
+
+impl fmt::Display for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_tokens(f, &self.tokens())
+    }
+}
+
+impl fmt::Display for Form<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tokens = Vec::new();
+        self.push_tokens(&mut tokens);
+        write_tokens(f, &tokens)
+    }
+}
@@ -0,0 +1,156 @@
+use std::iter::Peekable;
+use std::mem::take;
+
+use tokenizer::token::{Token, TokenType};
+
+use crate::node::{Form, Node, Prefixed, Seq, SyntaxTree};
+
+/// How deep forms nest in the tree. A form with as many enclosing forms is kept as `Form::TooDeep`
+pub const MAX_DEPTH: usize = 256;
+
+/// Recursive descent over the tokens.
+///
+/// Trivia is collected while looking for the next form and attached to it,
+/// or to the enclosing sequence or the tree if no form follows.
+pub(crate) struct Parser<'a, I: Iterator<Item = Token<'a>>> {
+    tokens: Peekable<I>,
+    /// Trivia read but not attached yet
+    trivia: Vec<Token<'a>>,
+    /// Forms enclosing the one being read
+    depth: usize,
+}
+
+/// The number of forms the token applies to
+fn arity(token: &Token) -> usize {
+    match token.kind() {
+        TokenType::Metadata => 2,
+        TokenType::ReaderConditional
+        | TokenType::ReaderConditionalSplicing
+        | TokenType::Quote
+        | TokenType::SynQuote
+        | TokenType::Unquote
+        | TokenType::UnquoteSplicing
+        | TokenType::Deref
+        | TokenType::VarQuote
+        | TokenType::Tag
+        | TokenType::Discard
+        | TokenType::NamespacedMap
+        | TokenType::Dispatch => 1,
+        _ => 0,
+    }
+}
+
+impl<'a, I: Iterator<Item = Token<'a>>> Parser<'a, I> {
+    pub(crate) fn new(tokens: I) -> Self {
+        Parser { tokens: tokens.peekable(), trivia: Vec::new(), depth: 0 }
+    }
+
+    pub(crate) fn parse(mut self) -> SyntaxTree<'a> {
+        let mut forms = Vec::new();
+        loop {
+            while let Some(node) = self.node() {
+                forms.push(node);
+            }
+            // A closing delimiter at the top level, which `node` doesn't consume
+            let Some(close) = self.tokens.next() else {
+                break;
+            };
+            forms.push(Node { trivia: take(&mut self.trivia), form: Form::Unmatched(close) });
+        }
+
+        SyntaxTree { forms, trivia: self.trivia }
+    }
+
+    /// Skips trivia, returns the next token without consuming it
+    fn peek(&mut self) -> Option<Token<'a>> {
+        while let Some(token) = self.tokens.next_if(Token::is_trivia) {
+            self.trivia.push(token);
+        }
+        self.tokens.peek().copied()
+    }
+
+    /// The next form, `None` at a closing delimiter or the end of the source
+    fn node(&mut self) -> Option<Node<'a>> {
+        let token = self.peek().filter(|token| !token.is_right())?;
+        self.tokens.next();
+        let trivia = take(&mut self.trivia);
+        if self.depth == MAX_DEPTH {
+            return Some(Node { trivia, form: Form::TooDeep(self.too_deep(token)) });
+        }
+
+        self.depth += 1;
+        let form = match token.kind() {
+            TokenType::LParen => Form::List(self.seq(token)),
+            TokenType::LBracket => Form::Vector(self.seq(token)),
+            TokenType::LBrace => Form::Map(self.seq(token)),
+            TokenType::LSet => Form::Set(self.seq(token)),
+            TokenType::LAnonFn => Form::Fn(self.seq(token)),
+            TokenType::ReaderConditional | TokenType::ReaderConditionalSplicing => {
+                Form::ReaderConditional(self.prefixed(token))
+            },
+            TokenType::Quote
+            | TokenType::SynQuote
+            | TokenType::Unquote
+            | TokenType::UnquoteSplicing
+            | TokenType::Deref
+            | TokenType::VarQuote => Form::Quote(self.prefixed(token)),
+            TokenType::Tag => Form::Tagged(self.prefixed(token)),
+            TokenType::Discard => Form::Discard(self.prefixed(token)),
+            TokenType::NamespacedMap => Form::NamespacedMap(self.prefixed(token)),
+            TokenType::Dispatch => Form::Dispatch(self.prefixed(token)),
+            TokenType::Metadata => Form::Metadata {
+                marker: token,
+                meta: self.node().map(Box::new),
+                form: self.node().map(Box::new),
+            },
+            _ => Form::Atom(token),
+        };
+        self.depth -= 1;
+
+        Some(Node { trivia, form })
+    }
+
+    /// The tokens of the form starting with `first`, read without building nodes
+    fn too_deep(&mut self, first: Token<'a>) -> Vec<Token<'a>> {
+        let mut tokens = Vec::new();
+        // Forms left to read and open delimiters
+        let mut forms = 1;
+        let mut depth = 0usize;
+        let mut next = Some(first);
+        while let Some(token) = next {
+            tokens.push(token);
+            if token.is_left() {
+                depth += 1;
+            } else if token.is_right() {
+                depth -= 1;
+            } else if depth == 0 && !token.is_trivia() {
+                forms += arity(&token);
+            }
+            if depth == 0 && !token.is_trivia() && !token.is_left() {
+                forms -= 1;
+            }
+            if forms == 0 {
+                break;
+            }
+            // A closing delimiter outside the form belongs to the enclosing sequence
+            next = self.tokens.next_if(|token| depth > 0 || !token.is_right());
+        }
+        tokens
+    }
+
+    fn prefixed(&mut self, prefix: Token<'a>) -> Prefixed<'a> {
+        Prefixed { prefix, form: self.node().map(Box::new) }
+    }
+
+    /// Reads the children up to the closing delimiter, which may be of another kind
+    fn seq(&mut self, open: Token<'a>) -> Seq<'a> {
+        let mut children = Vec::new();
+        while let Some(node) = self.node() {
+            children.push(node);
+        }
+        // Trivia is skipped by `node`, so a closing delimiter or nothing is left
+        let close = self.tokens.next();
+
+        Seq { open, children, trivia: take(&mut self.trivia), close }
+    }
+}
@@ -0,0 +1,363 @@
+//! Width-aware layout of forms, after Wadler's "A prettier printer".
+//!
+//! Unlike the formatter, which keeps the line breaks of the source, the layout is
+//! chosen from scratch: a group is written on one line if it fits, otherwise its
+//! line breaks are taken. Whitespace of the source is dropped, comments are kept.
+
+use tokenizer::token::{Token, TokenType};
+
+use crate::node::{Form, Node, Prefixed, Seq, SyntaxTree};
+
+/// A document to lay out
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Doc<'a> {
+    Text(&'a str),
+    /// The text if the enclosing group is flat, a line break otherwise
+    Line(&'a str),
+    /// A line break which breaks all enclosing groups
+    HardLine,
+    Concat(Vec<Doc<'a>>),
+    /// Increases the indentation of the line breaks by the amount
+    Nest(usize, Box<Doc<'a>>),
+    /// Sets the indentation of the line breaks to the current column
+    Align(Box<Doc<'a>>),
+    /// Written flat if it fits in the rest of the line
+    Group(Box<Doc<'a>>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+impl<'a> Doc<'a> {
+    pub fn nest(indent: usize, doc: Doc<'a>) -> Doc<'a> {
+        Doc::Nest(indent, Box::new(doc))
+    }
+
+    pub fn align(doc: Doc<'a>) -> Doc<'a> {
+        Doc::Align(Box::new(doc))
+    }
+
+    pub fn group(doc: Doc<'a>) -> Doc<'a> {
+        Doc::Group(Box::new(doc))
+    }
+
+    /// Lays the document out to fit in `width` columns where possible
+    pub fn render(&self, width: usize) -> String {
+        let mut out = String::new();
+        let mut column = 0;
+        let mut stack = vec![(0, Mode::Break, self)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Text(text) => push(&mut out, &mut column, text),
+                Doc::Line(text) if mode == Mode::Flat => push(&mut out, &mut column, text),
+                Doc::Line(_) | Doc::HardLine => {
+                    out.push('\n');
+                    out.extend(std::iter::repeat_n(' ', indent));
+                    column = indent;
+                },
+                Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+                Doc::Nest(n, doc) => stack.push((indent + n, mode, doc)),
+                Doc::Align(doc) => stack.push((column, mode, doc)),
+                Doc::Group(doc) => {
+                    let flat = mode == Mode::Flat || fits(width as isize - column as isize, doc, &stack);
+                    stack.push((indent, if flat { Mode::Flat } else { Mode::Break }, doc));
+                },
+            }
+        }
+        out
+    }
+}
+
+fn push(out: &mut String, column: &mut usize, text: &str) {
+    out.push_str(text);
+    *column = match text.rfind('\n') {
+        Some(i) => width(&text[i + 1..]),
+        None => *column + width(text),
+    };
+}
+
+/// Width of the text up to the first line break, which ends the line
+fn line_width(text: &str) -> (isize, bool) {
+    let line = text.split('\n').next().unwrap_or_default();
+    (width(line) as isize, line.len() < text.len())
+}
+
+/// True if the document laid out flat and what follows up to the next line break
+/// fit in the remaining width
+fn fits(mut remaining: isize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut rest = rest.iter().rev().map(|&(_, mode, doc)| (mode, doc));
+    let mut stack = vec![(Mode::Flat, doc)];
+    while remaining >= 0 {
+        let Some((mode, doc)) = stack.pop().or_else(|| rest.next()) else {
+            return true;
+        };
+        let text = match doc {
+            Doc::Text(text) => text,
+            Doc::Line(text) if mode == Mode::Flat => text,
+            Doc::HardLine if mode == Mode::Flat => return false,
+            Doc::Line(_) | Doc::HardLine => return true,
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (mode, doc)));
+                continue;
+            },
+            Doc::Nest(_, doc) | Doc::Align(doc) | Doc::Group(doc) => {
+                stack.push((mode, doc));
+                continue;
+            },
+        };
+        let (width, ends_line) = line_width(text);
+        remaining -= width;
+        if ends_line {
+            return remaining >= 0;
+        }
+    }
+    false
+}
+
+/// Comments of the trivia, each on its own line
+fn comments<'a>(trivia: &[Token<'a>]) -> Vec<Doc<'a>> {
+    trivia
+        .iter()
+        .filter(|token| matches!(token.kind(), TokenType::Comment | TokenType::Shebang))
+        .flat_map(|token| [Doc::Text(token.value().trim_end()), Doc::HardLine])
+        .collect()
+}
+
+/// Splits off the comment on the line of the preceding form, which stays after that form
+fn split_trailing<'t, 'a>(trivia: &'t [Token<'a>]) -> (Option<Token<'a>>, &'t [Token<'a>]) {
+    let inline = trivia
+        .iter()
+        .take_while(|token| token.kind() == TokenType::Whitespace && !token.value().contains('\n'))
+        .count();
+    match trivia.get(inline) {
+        Some(token) if token.kind() == TokenType::Comment => (Some(*token), &trivia[inline + 1..]),
+        _ => (None, trivia),
+    }
+}
+
+/// A form of a sequence with its comments
+struct Element<'a> {
+    doc: Doc<'a>,
+    /// Preceded by comments on their own lines
+    leading: bool,
+    /// Followed by a comment on its line, so a line break must follow
+    trailing: bool,
+}
+
+/// The nodes with the comments before them and the comment on their line after them,
+/// and the rest of the trivia after the last node
+fn elements<'t, 'a>(nodes: &'t [Node<'a>], after: &'t [Token<'a>]) -> (Vec<Element<'a>>, &'t [Token<'a>]) {
+    let mut elements = Vec::new();
+    let mut trivia = nodes.first().map_or(after, |node| node.trivia.as_slice());
+    for (i, node) in nodes.iter().enumerate() {
+        let (comment, rest) = split_trailing(nodes.get(i + 1).map_or(after, |next| &next.trivia));
+        let mut docs = comments(trivia);
+        let leading = !docs.is_empty();
+        docs.push(node.form.to_doc());
+        if let Some(comment) = comment {
+            docs.extend([Doc::Text(" "), Doc::Text(comment.value().trim_end())]);
+        }
+        elements.push(Element { doc: Doc::Concat(docs), leading, trailing: comment.is_some() });
+        trivia = rest;
+    }
+    (elements, trivia)
+}
+
+/// Breaks the line after a comment, otherwise only if the group doesn't fit
+fn separator<'a>(after_comment: bool) -> Doc<'a> {
+    if after_comment { Doc::HardLine } else { Doc::Line(" ") }
+}
+
+impl<'a> Node<'a> {
+    /// The form preceded by the comments of its trivia
+    pub fn to_doc(&self) -> Doc<'a> {
+        let mut docs = comments(&self.trivia);
+        docs.push(self.form.to_doc());
+        Doc::Concat(docs)
+    }
+}
+
+impl<'a> Form<'a> {
+    /// Maps are broken one entry per line, sequences of atoms are filled
+    /// up to the width, other sequences are broken one element per line.
+    /// A comment after an element on its line stays there
+    pub fn to_doc(&self) -> Doc<'a> {
+        match self {
+            Form::Atom(token) | Form::Unmatched(token) => Doc::Text(token.value()),
+            // Written as it is in the source
+            Form::TooDeep(tokens) => Doc::Concat(tokens.iter().map(|token| Doc::Text(token.value())).collect()),
+            Form::Map(seq) => {
+                let (elements, trivia) = elements(&seq.children, &seq.trivia);
+                let last_comment = elements.last().is_some_and(|element| element.trailing);
+                seq.to_doc(map_entries(&seq.children, elements), last_comment, trivia)
+            },
+            Form::List(seq) | Form::Vector(seq) | Form::Set(seq) | Form::Fn(seq) => {
+                let (elements, trivia) = elements(&seq.children, &seq.trivia);
+                let fill = seq.children.iter().all(|node| matches!(node.form, Form::Atom(_)))
+                    && elements.iter().all(|element| !element.leading);
+                let mut docs = Vec::new();
+                let mut after_comment = false;
+                for (i, element) in elements.into_iter().enumerate() {
+                    if i == 0 {
+                        docs.push(element.doc);
+                    } else if fill {
+                        docs.push(Doc::group(Doc::Concat(vec![separator(after_comment), element.doc])));
+                    } else {
+                        docs.extend([separator(after_comment), element.doc]);
+                    }
+                    after_comment = element.trailing;
+                }
+                seq.to_doc(docs, after_comment, trivia)
+            },
+            Form::Tagged(prefixed) => prefixed.to_doc(" "),
+            Form::ReaderConditional(prefixed)
+            | Form::Quote(prefixed)
+            | Form::Discard(prefixed)
+            | Form::NamespacedMap(prefixed)
+            | Form::Dispatch(prefixed) => prefixed.to_doc(""),
+            Form::Metadata { marker, meta, form } => {
+                let mut docs = vec![Doc::Text(marker.value())];
+                docs.extend(meta.iter().map(|meta| meta.to_doc()));
+                if let Some(form) = form {
+                    docs.extend([Doc::Text(" "), form.to_doc()]);
+                }
+                Doc::Concat(docs)
+            },
+        }
+    }
+}
+
+/// Keys and values separated by a space, a value which doesn't fit goes on the
+/// next line unless it is a collection, which breaks itself instead
+fn map_entries<'a>(children: &[Node<'a>], elements: Vec<Element<'a>>) -> Vec<Doc<'a>> {
+    let mut docs = Vec::new();
+    let mut elements = elements.into_iter();
+    let mut after_comment = false;
+    for entry in children.chunks(2) {
+        let Some(key) = elements.next() else {
+            break;
+        };
+        if !docs.is_empty() {
+            docs.push(separator(after_comment));
+        }
+        let Some(value) = elements.next() else {
+            after_comment = key.trailing;
+            docs.push(key.doc);
+            continue;
+        };
+        after_comment = value.trailing;
+        if key.trailing {
+            docs.push(Doc::Concat(vec![key.doc, Doc::nest(2, Doc::Concat(vec![Doc::HardLine, value.doc]))]));
+        } else if entry[1].form.seq().is_some() && !value.leading {
+            docs.push(Doc::Concat(vec![key.doc, Doc::Text(" "), value.doc]));
+        } else {
+            docs.push(Doc::group(Doc::Concat(vec![key.doc, Doc::nest(2, Doc::Concat(vec![Doc::Line(" "), value.doc]))])));
+        }
+    }
+    docs
+}
+
+impl<'a> Seq<'a> {
+    /// The delimiters around the elements, which are aligned after the opening one.
+    /// The closing one goes on its own line after comments
+    fn to_doc(&self, mut docs: Vec<Doc<'a>>, last_comment: bool, trivia: &[Token<'a>]) -> Doc<'a> {
+        let trailing = comments(trivia);
+        if !trailing.is_empty() || last_comment {
+            if !docs.is_empty() {
+                docs.push(Doc::HardLine);
+            }
+            docs.extend(trailing);
+        }
+        let close = self.close.map_or(Doc::Concat(vec![]), |close| Doc::Text(close.value()));
+        Doc::group(Doc::Concat(vec![Doc::Text(self.open.value()), Doc::align(Doc::Concat(docs)), close]))
+    }
+}
+
+impl<'a> Prefixed<'a> {
+    fn to_doc(&self, separator: &'a str) -> Doc<'a> {
+        match &self.form {
+            Some(form) => Doc::Concat(vec![Doc::Text(self.prefix.value()), Doc::Text(separator), form.to_doc()]),
+            None => Doc::Text(self.prefix.value()),
+        }
+    }
+}
+
+impl<'a> SyntaxTree<'a> {
+    /// Top-level forms, each starting on a new line, and the comments between them.
+    /// A blank line between forms in the source is kept
+    pub fn to_doc(&self) -> Doc<'a> {
+        let (elements, trivia) = elements(&self.forms, &self.trivia);
+        let mut docs = Vec::new();
+        for (i, (node, element)) in self.forms.iter().zip(elements).enumerate() {
+            let blank = node.trivia.iter().any(|token| token.kind() == TokenType::Whitespace && token.value().matches('\n').count() > 1);
+            if i > 0 && blank {
+                docs.push(Doc::HardLine);
+            }
+            docs.extend([element.doc, Doc::HardLine]);
+        }
+        docs.extend(comments(trivia));
+        Doc::Concat(docs)
+    }
+}
+
+/// Lays out the source to fit in `width` columns where possible
+pub fn pretty(source: &str, width: usize) -> String {
+    crate::parse(source).to_doc().render(width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pretty;
+
+    #[test]
+    fn fits() {
+        assert_eq!(pretty("{:a   1,\n :b [1 2]}  #{x}", 80), "{:a 1 :b [1 2]}\n#{x}\n");
+        assert_eq!(pretty("^:k (f\n 'a #_b #inst\n \"2024\")", 80), "^:k (f 'a #_b #inst \"2024\")\n");
+        assert_eq!(pretty("(a (b", 80), "(a (b\n");
+    }
+
+    #[test]
+    fn maps() {
+        assert_eq!(pretty("{:a 1 :b {:c \"long string\" :d 2} :e 3}", 21), concat!(
+            "{:a 1\n",
+            " :b {:c \"long string\"\n",
+            "     :d 2}\n",
+            " :e 3}\n",
+        ));
+        assert_eq!(pretty("{:description \"a string too long for the line\"}", 20), concat!(
+            "{:description\n",
+            "   \"a string too long for the line\"}\n",
+        ));
+        assert_eq!(pretty("{:odd}", 3), "{:odd}\n");
+    }
+
+    #[test]
+    fn sequences() {
+        assert_eq!(pretty("[1 2 3 4 5 6 7 8 9 10 11 12]", 12), "[1 2 3 4 5 6\n 7 8 9 10 11\n 12]\n");
+        assert_eq!(pretty("[{:a 1} {:b 2}]", 12), "[{:a 1}\n {:b 2}]\n");
+        assert_eq!(pretty("#{[1 2] [3 4]}", 10), "#{[1 2]\n  [3 4]}\n");
+    }
+
+    #[test]
+    fn comments() {
+        let source = ";; head\n\n\n[1 ; one\n 2 ; two\n]\n\n{:a 1} ; tail\n";
+        assert_eq!(pretty(source, 80), ";; head\n[1 ; one\n 2 ; two\n ]\n\n{:a 1} ; tail\n");
+        assert_eq!(pretty("(f ; call\n ; args\n a b)", 80), "(f ; call\n ; args\n a\n b)\n");
+    }
+
+    #[test]
+    fn map_comments() {
+        let source = "{:a 1 ; one\n :b ; key\n [2] :c 3 ; three\n}";
+        let expected = "{:a 1 ; one\n :b ; key\n   [2]\n :c 3 ; three\n }\n";
+        assert_eq!(pretty(source, 80), expected);
+        assert_eq!(pretty(expected, 80), expected);
+        assert_eq!(pretty("{:a 1, :b 2} ; map", 14), "{:a 1\n :b 2} ; map\n");
+    }
+}
@@ -0,0 +1,442 @@
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+
+use tokenizer::number::Number;
+use tokenizer::span::Position;
+use tokenizer::token::{Token, TokenType};
+
+use crate::error::{Error, ErrorKind};
+use crate::lexer::{self, Lexer};
+use crate::number::{decimal_to_f64, ratio_to_f64};
+
+/// Deserializes EDN straight from the tokens, without building a `Value`.
+///
+/// Keywords and symbols are visited as strings without the colon, so they name struct
+/// fields and enum variants. Lists, vectors and sets are sequences, maps are maps or structs.
+/// Tags are transparent: `#inst "2024"` is the string `"2024"`.
+/// Ratios and `BigDecimal`s are read as floats only if a float has their exact value,
+/// numbers read as strings are their digits.
+/// An enum variant with data is a map with a single entry, e.g. `{:circle 1.5}`.
+pub struct Deserializer<'de> {
+    lexer: Lexer<'de>,
+    /// Namespace of the next map key in a `#:ns{...}` map
+    key_namespace: Option<&'de str>,
+}
+
+/// Deserializes the only value of the source
+pub fn from_str<'de, T: de::Deserialize<'de>>(source: &'de str) -> Result<T, Error> {
+    let mut deserializer = Deserializer::from_str(source);
+    let value = T::deserialize(&mut deserializer)?;
+    match deserializer.lexer.next()? {
+        Some(token) => Err(Error::new(ErrorKind::ExpectedEnd, token.start())),
+        None => Ok(value),
+    }
+}
+
+/// Gives errors of the visitor the position of the value, unless they already have one
+fn locate<T>(result: Result<T, Error>, position: Position) -> Result<T, Error> {
+    result.map_err(|mut error| {
+        if error.position.line == 0 {
+            error.position = position;
+        }
+        error
+    })
+}
+
+impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(source: &'de str) -> Self {
+        Deserializer { lexer: Lexer::new(source), key_namespace: None }
+    }
+
+    /// The first token of the next value, skipping tags
+    fn value_token(&mut self) -> Result<Token<'de>, Error> {
+        let Some(mut token) = self.lexer.next()? else {
+            return Err(Error::new(ErrorKind::MissingValue, self.lexer.end()));
+        };
+        while token.kind() == TokenType::Tag {
+            token = self.lexer.next_value(token)?;
+        }
+        if token.is_right() {
+            return Err(lexer::unexpected(token, None));
+        }
+        Ok(token)
+    }
+
+    /// Consumes the closing delimiter of `open`
+    fn close(&mut self, open: Token) -> Result<(), Error> {
+        match self.lexer.next()? {
+            Some(close) if lexer::closes(open, close) => Ok(()),
+            Some(close) if close.is_right() => Err(lexer::unexpected(close, Some(open))),
+            Some(token) => Err(Error::new(ErrorKind::TrailingElements, token.start())),
+            None => Err(lexer::unclosed(open)),
+        }
+    }
+
+    fn seq<V: Visitor<'de>>(&mut self, open: Token<'de>, visitor: V) -> Result<V::Value, Error> {
+        self.lexer.enter(open)?;
+        let value = locate(visitor.visit_seq(Seq { de: self, open }), open.start())?;
+        self.close(open)?;
+        self.lexer.leave();
+        Ok(value)
+    }
+
+    fn map<V: Visitor<'de>>(&mut self, open: Token<'de>, namespace: Option<&'de str>, visitor: V) -> Result<V::Value, Error> {
+        self.lexer.enter(open)?;
+        let value = locate(visitor.visit_map(Map { de: self, open, namespace }), open.start())?;
+        self.close(open)?;
+        self.lexer.leave();
+        Ok(value)
+    }
+}
+
+/// A keyword or a symbol, qualified with the namespace of a `#:ns{...}` map key
+fn name<'de, V: Visitor<'de>>(name: &'de str, namespace: Option<&'de str>, visitor: V) -> Result<V::Value, Error> {
+    match (namespace, name.split_once('/')) {
+        (Some(_), Some(("_", name))) => visitor.visit_borrowed_str(name),
+        (Some(namespace), None) => visitor.visit_string(format!("{}/{}", namespace, name)),
+        _ => visitor.visit_borrowed_str(name),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let namespace = self.key_namespace.take();
+        let token = self.value_token()?;
+        let value = match token.kind() {
+            TokenType::Symbol => match token.value() {
+                "nil" => visitor.visit_unit(),
+                "true" => visitor.visit_bool(true),
+                "false" => visitor.visit_bool(false),
+                symbol => name(symbol, namespace, visitor),
+            },
+            TokenType::Keyword => {
+                lexer::atom(token)?;
+                name(&token.value()[1..], namespace, visitor)
+            },
+            TokenType::Number => match token.number() {
+                Ok(Number::Long(n)) => visitor.visit_i64(n),
+                Ok(Number::BigInt(digits)) => match digits.parse() {
+                    Ok(n) => visitor.visit_i128(n),
+                    Err(_) => visitor.visit_string(digits),
+                },
+                Ok(Number::Double(n)) => visitor.visit_f64(n),
+                Ok(Number::Ratio { numerator, denominator }) => match ratio_to_f64(&numerator, &denominator) {
+                    Some(n) => visitor.visit_f64(n),
+                    None => return Err(Error::new(ErrorKind::Inexact(token.value().to_owned()), token.start())),
+                },
+                Ok(Number::BigDecimal(digits)) => match decimal_to_f64(&digits) {
+                    Some(n) => visitor.visit_f64(n),
+                    None => return Err(Error::new(ErrorKind::Inexact(token.value().to_owned()), token.start())),
+                },
+                Err(e) => return Err(Error::new(ErrorKind::InvalidNumber(e), token.start())),
+            },
+            TokenType::String => {
+                let raw = &token.value()[1..token.value().len() - 1];
+                match lexer::atom(token)? {
+                    // Without escapes the text can be borrowed
+                    crate::Value::String(s) if s == raw => visitor.visit_borrowed_str(raw),
+                    crate::Value::String(s) => visitor.visit_string(s),
+                    _ => unreachable!("a string token is a string"),
+                }
+            },
+            TokenType::Character | TokenType::SymbolicValue => match lexer::atom(token)? {
+                crate::Value::Char(c) => visitor.visit_char(c),
+                crate::Value::Float(n) => visitor.visit_f64(n),
+                _ => unreachable!("characters and symbolic values are chars and floats"),
+            },
+            TokenType::LParen | TokenType::LBracket | TokenType::LSet => return self.seq(token, visitor),
+            TokenType::LBrace => return self.map(token, None, visitor),
+            TokenType::NamespacedMap => {
+                let namespace = token.value().strip_prefix("#:").filter(|ns| !ns.starts_with(':'));
+                let open = self.lexer.next_value(token)?;
+                match (namespace, open.kind()) {
+                    (Some(namespace), TokenType::LBrace) => return self.map(open, Some(namespace), visitor),
+                    _ => {
+                        let error = tokenizer::error::ErrorKind::InvalidNamespacedMap;
+                        return Err(Error::new(ErrorKind::Syntax(error), token.start()));
+                    },
+                }
+            },
+            kind => unreachable!("{:?} doesn't start a value", kind),
+        };
+        locate(value, token.start())
+    }
+
+    /// Numbers which don't fit into `i64` or `f64` are visited as their digits, e.g. `1/3` as `"1/3"`
+    /// and `1.50M` as `"1.50"`, so they are read without losing precision
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let token = self.value_token()?;
+        let digits = match token.kind() {
+            TokenType::Number => match token.number() {
+                Ok(Number::BigInt(digits) | Number::BigDecimal(digits)) => Some(digits),
+                Ok(Number::Ratio { numerator, denominator }) => Some(format!("{}/{}", numerator, denominator)),
+                _ => None,
+            },
+            _ => None,
+        };
+        match digits {
+            Some(digits) => locate(visitor.visit_string(digits), token.start()),
+            None => {
+                self.lexer.unread(token);
+                self.deserialize_any(visitor)
+            },
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    /// Skips the value like `#_` does, checking the delimiters only
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.key_namespace = None;
+        let token = self.value_token()?;
+        self.lexer.skip_rest(token)?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let token = self.value_token()?;
+        if token.kind() == TokenType::Symbol && token.value() == "nil" {
+            return locate(visitor.visit_none(), token.start());
+        }
+        self.lexer.unread(token);
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.key_namespace = None;
+        let token = self.value_token()?;
+        let value = match token.kind() {
+            TokenType::LBrace => {
+                self.lexer.enter(token)?;
+                let value = visitor.visit_enum(Enum { de: self })?;
+                self.close(token)?;
+                self.lexer.leave();
+                Ok(value)
+            },
+            TokenType::Keyword => visitor.visit_enum(token.value()[1..].into_deserializer()),
+            TokenType::Symbol => visitor.visit_enum(token.value().into_deserializer()),
+            TokenType::String => match lexer::atom(token)? {
+                crate::Value::String(s) => visitor.visit_enum(s.into_deserializer()),
+                _ => unreachable!("a string token is a string"),
+            },
+            _ => Err(de::Error::custom("expected a keyword or a map with a single entry for an enum")),
+        };
+        locate(value, token.start())
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct Seq<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    open: Token<'de>,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        match self.de.lexer.peek()? {
+            Some(token) if token.is_right() => Ok(None),
+            Some(_) => seed.deserialize(&mut *self.de).map(Some),
+            None => Err(lexer::unclosed(self.open)),
+        }
+    }
+}
+
+struct Map<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    open: Token<'de>,
+    namespace: Option<&'de str>,
+}
+
+impl<'de> MapAccess<'de> for Map<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        match self.de.lexer.peek()? {
+            Some(token) if token.is_right() => Ok(None),
+            Some(_) => {
+                self.de.key_namespace = self.namespace;
+                let key = seed.deserialize(&mut *self.de);
+                self.de.key_namespace = None;
+                key.map(Some)
+            },
+            None => Err(lexer::unclosed(self.open)),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.de.lexer.peek()? {
+            Some(token) if token.is_right() => Err(Error::new(ErrorKind::OddMap, self.open.start())),
+            _ => seed.deserialize(&mut *self.de),
+        }
+    }
+}
+
+/// A variant with data, written as a map with a single entry
+struct Enum<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for Enum<'_, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Enum<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::de::IgnoredAny;
+    use serde::Deserialize;
+
+    use super::from_str;
+    use crate::error::ErrorKind;
+    use crate::lexer::MAX_DEPTH;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Level {
+        Debug,
+        Info,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Shape {
+        Circle(f64),
+        Rect { width: u32, height: u32 },
+        Point,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Config<'a> {
+        port: u16,
+        #[serde(borrow)]
+        hosts: Vec<&'a str>,
+        log_level: Level,
+        timeout: Option<f64>,
+        shapes: Vec<Shape>,
+        limits: BTreeMap<String, (i32, char)>,
+    }
+
+    #[test]
+    fn config() {
+        let source = r#"
+            ;; Service config
+            {:port 8080
+             :hosts ["a" #_"skipped" "b"]
+             :log-level :info
+             :timeout nil
+             :shapes [{:circle 1.5} {:rect {:width 2 :height 3}} :point]
+             :limits {:cpu [2 \c] "mem" [-1 \m]}}"#;
+        let config: Config = from_str(source).unwrap();
+        assert_eq!(config, Config {
+            port: 8080,
+            hosts: vec!["a", "b"],
+            log_level: Level::Info,
+            timeout: None,
+            shapes: vec![Shape::Circle(1.5), Shape::Rect { width: 2, height: 3 }, Shape::Point],
+            limits: BTreeMap::from([("cpu".to_owned(), (2, 'c')), ("mem".to_owned(), (-1, 'm'))]),
+        });
+        assert_eq!(from_str::<Level>("debug"), Ok(Level::Debug));
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(from_str::<Option<i64>>("42"), Ok(Some(42)));
+        assert_eq!(from_str::<Vec<f64>>("(1.5 3/2 ##Inf 2)"), Ok(vec![1.5, 1.5, f64::INFINITY, 2.0]));
+        assert_eq!(from_str::<String>("\"a\\nb\""), Ok("a\nb".to_owned()));
+        assert_eq!(from_str::<Vec<String>>("#{:a/b sym}"), Ok(vec!["a/b".to_owned(), "sym".to_owned()]));
+        assert_eq!(from_str::<String>("#inst \"2024-01-01\""), Ok("2024-01-01".to_owned()));
+        assert_eq!(from_str::<i128>("99999999999999999999"), Ok(99999999999999999999));
+        assert_eq!(from_str::<Vec<f64>>("[2/4 1.25M -0.5e1M]"), Ok(vec![0.5, 1.25, -5.0]));
+        assert_eq!(from_str::<Vec<String>>("[1/3 0.1M 1N #my/tag 2/4 \"s\"]"), Ok(["1/3", "0.1", "1", "2/4", "s"].map(str::to_owned).to_vec()));
+        assert_eq!(from_str::<Vec<IgnoredAny>>("[1/3 0.1M]").map(|values| values.len()), Ok(2));
+
+        let map: BTreeMap<String, i32> = from_str("#:a{:b 1 :_/c 2 :d/e 3}").unwrap();
+        assert_eq!(map, BTreeMap::from([("a/b".to_owned(), 1), ("c".to_owned(), 2), ("d/e".to_owned(), 3)]));
+    }
+
+    #[test]
+    fn errors() {
+        let error = from_str::<Config>("{:port 8080\n :hosts [\"a\" 1]}").unwrap_err();
+        assert_eq!(error.to_string(), "2:14: invalid type: integer `1`, expected a borrowed string");
+
+        let error = from_str::<Config>("{:port 8080}").unwrap_err();
+        assert_eq!(error.to_string(), "1:1: missing field `hosts`");
+
+        let error = from_str::<Level>(":warn").unwrap_err();
+        assert_eq!(error.to_string(), "1:1: unknown variant `warn`, expected `debug` or `info`");
+
+        let error = from_str::<(i32, i32)>("[1 2 3]").unwrap_err();
+        assert_eq!(error.to_string(), "1:6: more elements than expected");
+        assert_eq!(from_str::<(i32, i32)>("[1 2)").unwrap_err().kind, ErrorKind::Mismatched { expected: ']', found: ')' });
+
+        let error = from_str::<Vec<f64>>("[0.5M\n 1/3]").unwrap_err();
+        assert_eq!(error.to_string(), "2:2: `1/3` can't be read as a float without losing precision");
+        assert_eq!(from_str::<f64>("0.1M").unwrap_err().kind, ErrorKind::Inexact("0.1M".to_owned()));
+
+        assert_eq!(from_str::<i32>("1 2").unwrap_err().kind, ErrorKind::ExpectedEnd);
+        assert_eq!(from_str::<BTreeMap<String, i32>>("{:a}").unwrap_err().kind, ErrorKind::OddMap);
+        assert_eq!(from_str::<Vec<i32>>("[1").unwrap_err().kind, ErrorKind::Unclosed("[".to_owned()));
+        assert_eq!(from_str::<i32>("").unwrap_err().kind, ErrorKind::MissingValue);
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Nested(Vec<Nested>);
+
+    #[test]
+    fn deep_nesting() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        assert!(from_str::<Nested>(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(from_str::<Nested>(&nested(MAX_DEPTH + 1)).unwrap_err().kind, ErrorKind::TooDeep);
+        assert_eq!(from_str::<IgnoredAny>(&nested(100_000)).unwrap_err().kind, ErrorKind::TooDeep);
+        assert_eq!(from_str::<IgnoredAny>(&"{:a ".repeat(100_000)).unwrap_err().kind, ErrorKind::TooDeep);
+        assert_eq!(from_str::<Vec<i32>>(&format!("[#_ {} 1]", nested(100_000))).unwrap_err().kind, ErrorKind::TooDeep);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+
+use tokenizer::error::ErrorKind as TokenError;
+use tokenizer::number::NumberError;
+use tokenizer::span::Position;
+use tokenizer::string::StringErrorKind;
+
+use crate::lexer::MAX_DEPTH;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ErrorKind {
+    /// An error token, including syntax which is not supported in EDN
+    Syntax(TokenError),
+    InvalidNumber(NumberError),
+    InvalidString(StringErrorKind),
+    /// The opening delimiter is not closed before the end of the input
+    Unclosed(String),
+    /// A closing delimiter of another kind than the opening one
+    Mismatched { expected: char, found: char },
+    /// A closing delimiter without an opening one
+    Unexpected(char),
+    /// The input, a tag or a discard ends without a value
+    MissingValue,
+    /// Values nested deeper than `MAX_DEPTH`
+    TooDeep,
+    /// More than one value where a single value is expected
+    ExpectedEnd,
+    /// A map with a key without a value
+    OddMap,
+    /// More elements than the type read from the sequence takes, e.g. a tuple
+    TrailingElements,
+    /// A ratio or a `BigDecimal` read as a number which no float has the exact value of,
+    /// e.g. `1/3`. Strings take the digits of any number
+    Inexact(String),
+    DuplicateKey(String),
+    DuplicateElement(String),
+    /// The tag handler rejected the value
+    InvalidTag { tag: String, message: String },
+    /// A field or variant name which can't be written as a keyword, e.g. `a b`
+    InvalidKeyword(String),
+    /// An error reported by a serde `Deserialize` or `Serialize` implementation
+    Custom(String),
+}
+
+/// An error with the position in the source where it's found
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// Line 0 if the error is not related to the source, e.g. when writing EDN
+    pub position: Position,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, position: Position) -> Self {
+        Error { kind, position }
+    }
+
+    /// Error which has no position yet
+    #[cfg(feature = "serde")]
+    fn custom(message: String) -> Self {
+        Error::new(ErrorKind::Custom(message), Position::new(0, 0, 0))
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Syntax(error) => write!(f, "{}", error),
+            ErrorKind::InvalidNumber(error) => write!(f, "{}", error),
+            ErrorKind::InvalidString(error) => write!(f, "{}", error),
+            ErrorKind::Unclosed(open) => write!(f, "unclosed `{}`", open),
+            ErrorKind::Mismatched { expected, found } => write!(f, "expected `{}`, found `{}`", expected, found),
+            ErrorKind::Unexpected(close) => write!(f, "unexpected `{}`", close),
+            ErrorKind::MissingValue => write!(f, "expected a value"),
+            ErrorKind::TooDeep => write!(f, "values nested deeper than {} levels", MAX_DEPTH),
+            ErrorKind::ExpectedEnd => write!(f, "expected the end of input after the value"),
+            ErrorKind::OddMap => write!(f, "map literal must contain an even number of forms"),
+            ErrorKind::TrailingElements => write!(f, "more elements than expected"),
+            ErrorKind::Inexact(number) => write!(f, "`{}` can't be read as a float without losing precision", number),
+            ErrorKind::DuplicateKey(key) => write!(f, "duplicate key: {}", key),
+            ErrorKind::DuplicateElement(element) => write!(f, "duplicate set element: {}", element),
+            ErrorKind::InvalidTag { tag, message } => write!(f, "invalid #{}: {}", tag, message),
+            ErrorKind::InvalidKeyword(name) => write!(f, "`{}` can't be written as a keyword", name),
+            ErrorKind::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.position.line == 0 {
+            return write!(f, "{}", self.kind);
+        }
+        write!(f, "{}:{}: {}", self.position.line, self.position.column, self.kind)
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::custom(msg.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::custom(msg.to_string())
+    }
+}
@@ -0,0 +1,195 @@
+use tokenizer::dialect::Dialect;
+use tokenizer::error::ErrorKind as TokenError;
+use tokenizer::number::Number;
+use tokenizer::span::Position;
+use tokenizer::token::{Token, TokenType};
+use tokenizer::Tokenizer;
+
+use crate::error::{Error, ErrorKind};
+use crate::number::reduce;
+use crate::value::{Keyword, Symbol, Value};
+
+/// How deep values nest, tags and discards included. Deeper input is an `ErrorKind::TooDeep` error
+pub const MAX_DEPTH: usize = 256;
+
+/// Significant tokens of EDN source.
+///
+/// Trivia and discarded forms are skipped, error tokens and syntax EDN doesn't have
+/// are reported as errors.
+pub(crate) struct Lexer<'a> {
+    tokenizer: Tokenizer<'a>,
+    peeked: Option<Token<'a>>,
+    /// End of the last token read from the tokenizer
+    end: Position,
+    /// Number of values being read which enclose the next token
+    depth: usize,
+}
+
+/// The closing delimiter of an opening delimiter token
+pub(crate) fn closing(open: Token) -> char {
+    match open.kind() {
+        TokenType::LParen => ')',
+        TokenType::LBracket => ']',
+        _ => '}',
+    }
+}
+
+pub(crate) fn closes(open: Token, close: Token) -> bool {
+    close.value().starts_with(closing(open))
+}
+
+pub(crate) fn unclosed(open: Token) -> Error {
+    Error::new(ErrorKind::Unclosed(open.value().to_owned()), open.start())
+}
+
+/// Error for a closing delimiter which doesn't close `open`, or any if `open` is `None`
+pub(crate) fn unexpected(close: Token, open: Option<Token>) -> Error {
+    let found = close.value().chars().next().unwrap_or_default();
+    let kind = match open {
+        Some(open) => ErrorKind::Mismatched { expected: closing(open), found },
+        None => ErrorKind::Unexpected(found),
+    };
+    Error::new(kind, close.start())
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        Lexer {
+            tokenizer: Tokenizer::from(source).with_dialect(Dialect::Edn),
+            peeked: None,
+            end: Position::default(),
+            depth: 0,
+        }
+    }
+
+    /// End of the input, where errors about a missing value are reported
+    pub(crate) fn end(&self) -> Position {
+        self.end
+    }
+
+    pub(crate) fn peek(&mut self) -> Result<Option<Token<'a>>, Error> {
+        while self.peeked.is_none() {
+            let Some(token) = self.tokenizer.next() else {
+                return Ok(None);
+            };
+            self.end = token.end();
+
+            match token.kind() {
+                _ if token.is_trivia() => (),
+                TokenType::Discard => self.skip_form(token)?,
+                TokenType::Error(error) => return Err(Error::new(ErrorKind::Syntax(error), token.start())),
+                _ => self.peeked = Some(token),
+            }
+        }
+        Ok(self.peeked)
+    }
+
+    pub(crate) fn next(&mut self) -> Result<Option<Token<'a>>, Error> {
+        self.peek()?;
+        Ok(self.peeked.take())
+    }
+
+    /// Puts back the token returned by `next`
+    #[cfg(feature = "serde")]
+    pub(crate) fn unread(&mut self, token: Token<'a>) {
+        debug_assert!(self.peeked.is_none());
+        self.peeked = Some(token);
+    }
+
+    /// The next token, which must start a value
+    pub(crate) fn next_value(&mut self, after: Token) -> Result<Token<'a>, Error> {
+        match self.next()? {
+            Some(token) if token.is_right() => Err(unexpected(token, None)),
+            Some(token) => Ok(token),
+            None => Err(Error::new(ErrorKind::MissingValue, after.end())),
+        }
+    }
+
+    /// Enters the value starting with `token`, which must be left with `leave`
+    pub(crate) fn enter(&mut self, token: Token) -> Result<(), Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::new(ErrorKind::TooDeep, token.start()));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Skips the form after `prefix`, checking the delimiters only
+    fn skip_form(&mut self, prefix: Token) -> Result<(), Error> {
+        self.enter(prefix)?;
+        let token = self.next_value(prefix)?;
+        self.skip_rest(token)?;
+        self.leave();
+        Ok(())
+    }
+
+    /// Skips the rest of the form starting with `token`
+    pub(crate) fn skip_rest(&mut self, token: Token) -> Result<(), Error> {
+        match token.kind() {
+            TokenType::Tag | TokenType::NamespacedMap => self.skip_form(token),
+            _ if token.is_left() => loop {
+                match self.next()? {
+                    Some(close) if closes(token, close) => return Ok(()),
+                    Some(close) if close.is_right() => return Err(unexpected(close, Some(token))),
+                    Some(inner) => {
+                        self.enter(inner)?;
+                        self.skip_rest(inner)?;
+                        self.leave();
+                    },
+                    None => return Err(unclosed(token)),
+                }
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Decodes a token which is a value by itself
+pub(crate) fn atom(token: Token) -> Result<Value, Error> {
+    let error = |kind| Error::new(kind, token.start());
+
+    match token.kind() {
+        TokenType::Symbol => match token.value() {
+            "nil" => Ok(Value::Nil),
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            _ => {
+                let symbol = token.symbol().map_err(|_| error(ErrorKind::Syntax(TokenError::InvalidSymbol)))?;
+                Ok(Value::Symbol(Symbol::from(symbol)))
+            },
+        },
+        TokenType::Keyword => match token.keyword() {
+            Ok(keyword) if !keyword.auto_resolved => Ok(Value::Keyword(Keyword::from(keyword))),
+            _ => Err(error(ErrorKind::Syntax(TokenError::InvalidKeyword))),
+        },
+        TokenType::Number => match token.number() {
+            Ok(Number::Long(n)) => Ok(Value::Integer(n)),
+            Ok(Number::BigInt(digits)) => Ok(Value::BigInt(digits)),
+            Ok(Number::Double(n)) => Ok(Value::Float(n)),
+            Ok(Number::Ratio { numerator, denominator }) => Ok(match reduce(&numerator, &denominator) {
+                (numerator, denominator) if denominator == "1" => match numerator.parse() {
+                    Ok(n) => Value::Integer(n),
+                    Err(_) => Value::BigInt(numerator),
+                },
+                (numerator, denominator) => Value::Ratio { numerator, denominator },
+            }),
+            Ok(Number::BigDecimal(digits)) => Ok(Value::BigDecimal(digits)),
+            Err(e) => Err(error(ErrorKind::InvalidNumber(e))),
+        },
+        TokenType::SymbolicValue => Ok(Value::Float(match token.value() {
+            "##Inf" => f64::INFINITY,
+            "##-Inf" => f64::NEG_INFINITY,
+            _ => f64::NAN,
+        })),
+        TokenType::String => token.string().map(Value::String).map_err(|errors| {
+            let first = errors[0];
+            Error::new(ErrorKind::InvalidString(first.kind), first.position)
+        }),
+        TokenType::Character => token.character().map(Value::Char).map_err(|_| error(ErrorKind::Syntax(TokenError::InvalidCharacter))),
+        _ => unreachable!("{:?} is not an atom", token.kind()),
+    }
+}
@@ -0,0 +1,241 @@
+//! EDN reader built on the tokenizer.
+//!
+//! ```
+//! let config = edn::parse("{:port 8080 :hosts [\"a\" \"b\"]}").unwrap();
+//! assert_eq!(config.get("port"), Some(&edn::Value::Integer(8080)));
+//! ```
+//!
+//! With the `serde` feature, which is on by default, types implementing
+//! `Deserialize` and `Serialize` are read and written with `from_str` and `to_string`.
+//! `Value::to_pretty_string` and `to_string_pretty` break the output into lines
+//! to fit a width.
+
+#[cfg(feature = "serde")]
+mod de;
+mod error;
+mod lexer;
+mod number;
+mod reader;
+#[cfg(feature = "serde")]
+mod ser;
+mod tags;
+mod value;
+
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+pub use de::{from_str, Deserializer};
+pub use error::{Error, ErrorKind};
+pub use lexer::MAX_DEPTH;
+pub use reader::{Reader, TagHandler};
+#[cfg(feature = "serde")]
+pub use ser::{to_string, to_string_pretty, Serializer};
+pub use tags::{Inst, Uuid};
+pub use value::{Keyword, Symbol, Value};
+
+/// Reads the only value of the source
+pub fn parse(source: &str) -> Result<Value, Error> {
+    Reader::new().read(source)
+}
+
+/// Reads all values of the source
+pub fn parse_all(source: &str) -> Result<Vec<Value>, Error> {
+    Reader::new().read_all(source)
+}
+
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+    use tokenizer::dialect::Dialect;
+    use tokenizer::error::ErrorKind as TokenError;
+    use tokenizer::number::NumberError;
+    use tokenizer::string::StringErrorKind;
+
+    use super::{parse, parse_all, ErrorKind, Keyword, Reader, Symbol, Value, MAX_DEPTH};
+
+    fn kw(name: &str) -> Value {
+        Value::Keyword(Keyword::new(None, name))
+    }
+
+    fn int(n: i64) -> Value {
+        Value::Integer(n)
+    }
+
+    /// Kind and `line:column` of the error
+    fn error(source: &str) -> (ErrorKind, String) {
+        let error = parse(source).unwrap_err();
+        (error.kind, format!("{}:{}", error.position.line, error.position.column))
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(parse("nil"), Ok(Value::Nil));
+        assert_eq!(parse("true"), Ok(Value::Bool(true)));
+        assert_eq!(parse("-42"), Ok(int(-42)));
+        assert_eq!(parse("42N"), Ok(Value::BigInt("42".to_owned())));
+        assert_eq!(parse("99999999999999999999"), Ok(Value::BigInt("99999999999999999999".to_owned())));
+        assert_eq!(parse("-1000000000000000000000000000000000000000N"), Ok(Value::BigInt("-1000000000000000000000000000000000000000".to_owned())));
+        assert_eq!(parse("0x100000000000000000000000000000000"), Ok(Value::BigInt("340282366920938463463374607431768211456".to_owned())));
+        assert_eq!(parse("1.5e3"), Ok(Value::Float(1500.0)));
+        assert_eq!(parse("1.50M"), Ok(Value::BigDecimal("1.50".to_owned())));
+        assert_eq!(parse("22/7"), Ok(Value::Ratio { numerator: "22".to_owned(), denominator: "7".to_owned() }));
+        assert_eq!(
+            parse("-100000000000000000000000000000000000000000/3"),
+            Ok(Value::Ratio { numerator: "-100000000000000000000000000000000000000000".to_owned(), denominator: "3".to_owned() }),
+        );
+        assert_eq!(parse("##-Inf"), Ok(Value::Float(f64::NEG_INFINITY)));
+        assert_eq!(parse("##NaN"), Ok(Value::Float(f64::NAN)));
+        assert_eq!(parse("\"a\\n\\u03bb\""), Ok(Value::String("a\nλ".to_owned())));
+        assert_eq!(parse("\\newline"), Ok(Value::Char('\n')));
+        assert_eq!(parse(":a/b"), Ok(Value::Keyword(Keyword::new(Some("a"), "b"))));
+        assert_eq!(parse("my.ns/sym"), Ok(Value::Symbol(Symbol::new(Some("my.ns"), "sym"))));
+    }
+
+    #[test]
+    fn numbers() {
+        let ratio = |numerator: &str, denominator: &str| Value::Ratio { numerator: numerator.to_owned(), denominator: denominator.to_owned() };
+        assert_eq!(parse("2/4"), Ok(ratio("1", "2")));
+        assert_eq!(parse("-6/4"), Ok(ratio("-3", "2")));
+        assert_eq!(parse("4/2"), Ok(int(2)));
+        assert_eq!(parse("0/5"), Ok(int(0)));
+        assert_eq!(parse("200000000000000000000/2"), Ok(Value::BigInt("100000000000000000000".to_owned())));
+        assert_eq!(parse("#{2/3 1/2 -1/2 1/3}").unwrap().to_string(), "#{-1/2 1/3 1/2 2/3}");
+        assert_eq!(ratio("2", "4"), ratio("1", "2"));
+
+        assert_eq!(parse("1.0M"), parse("1.00M"));
+        assert_eq!(parse("1.0M").unwrap().to_string(), "1.0M");
+        assert_eq!(error("#{10M 9M -10M -9M 1e1M}"), (ErrorKind::DuplicateElement("1e1M".to_owned()), "1:19".to_owned()));
+        assert_eq!(parse("#{10M 9M -10M -9M 0.0M}").unwrap().to_string(), "#{-10M -9M 0.0M 9M 10M}");
+
+        let set: HashSet<_> = [ratio("2", "4"), ratio("1", "2"), Value::BigDecimal("1.0".to_owned()), Value::BigDecimal("1.00".to_owned())].into();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn collections() {
+        let value = parse("{:a [1 (2 3)] :b #{4 5} :c {}}").unwrap();
+        let expected = BTreeMap::from([
+            (kw("a"), Value::Vector(vec![int(1), Value::List(vec![int(2), int(3)])])),
+            (kw("b"), Value::Set(BTreeSet::from([int(4), int(5)]))),
+            (kw("c"), Value::Map(BTreeMap::new())),
+        ]);
+        assert_eq!(value, Value::Map(expected));
+        assert_eq!(value.get("a").map(|v| v.to_string()), Some("[1 (2 3)]".to_owned()));
+
+        let value = parse("#{10N -2N 9N -10N 100000000000000000000000000000000000000000N}").unwrap();
+        assert_eq!(value.to_string(), "#{-10N -2N 9N 10N 100000000000000000000000000000000000000000N}");
+
+        let value = parse("#:a{:b 1 :_/c 2 :d/e 3 f 4}").unwrap();
+        assert_eq!(value.to_string(), "{:c 2, :a/b 1, :d/e 3, a/f 4}");
+    }
+
+    #[test]
+    fn discard_and_comments() {
+        assert_eq!(parse("; comment\n[1 #_ 2 #_ #_ 3 4 5 #_[6 (7)] #_ #tag 8] ; end"), Ok(Value::Vector(vec![int(1), int(5)])));
+        assert_eq!(parse_all("1 2 #_ 3"), Ok(vec![int(1), int(2)]));
+        assert_eq!(parse_all(" ;; nothing"), Ok(vec![]));
+    }
+
+    #[test]
+    fn tags() {
+        let value = parse("[#inst \"2024-01-02T03:04:05Z\" #uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\" #my/tag {:a 1}]").unwrap();
+        assert_eq!(
+            value.to_string(),
+            "[#inst \"2024-01-02T03:04:05.000-00:00\" #uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\" #my/tag {:a 1}]"
+        );
+
+        let reader = Reader::new()
+            .with_tag("my/point", |value| match value {
+                Value::Vector(coords) if coords.len() == 2 => Ok(Value::List(coords)),
+                _ => Err("expected [x y]".to_owned()),
+            })
+            .with_tag("inst", Ok);
+        assert_eq!(reader.read("#my/point [1 2]"), Ok(Value::List(vec![int(1), int(2)])));
+        assert_eq!(reader.read("#inst \"x\""), Ok(Value::String("x".to_owned())));
+
+        let error = reader.read("[\n #my/point [1]]").unwrap_err();
+        assert_eq!(error.to_string(), "2:2: invalid #my/point: expected [x y]");
+        assert_eq!(error.kind, ErrorKind::InvalidTag { tag: "my/point".to_owned(), message: "expected [x y]".to_owned() });
+    }
+
+    #[test]
+    fn errors() {
+        let unsupported = ErrorKind::Syntax(TokenError::UnsupportedSyntax(Dialect::Edn));
+        assert_eq!(error(""), (ErrorKind::MissingValue, "1:1".to_owned()));
+        assert_eq!(error("1 2"), (ErrorKind::ExpectedEnd, "1:3".to_owned()));
+        assert_eq!(error("[1\n (2]"), (ErrorKind::Mismatched { expected: ')', found: ']' }, "2:4".to_owned()));
+        assert_eq!(error("{:a [1"), (ErrorKind::Unclosed("[".to_owned()), "1:5".to_owned()));
+        assert_eq!(error(")"), (ErrorKind::Unexpected(')'), "1:1".to_owned()));
+        assert_eq!(error("{:a}"), (ErrorKind::OddMap, "1:1".to_owned()));
+        assert_eq!(error("{:a 1 :a 2}"), (ErrorKind::DuplicateKey(":a".to_owned()), "1:7".to_owned()));
+        assert_eq!(error("#{1 1}"), (ErrorKind::DuplicateElement("1".to_owned()), "1:5".to_owned()));
+        assert_eq!(error("[#_]"), (ErrorKind::Unexpected(']'), "1:4".to_owned()));
+        assert_eq!(error("#_"), (ErrorKind::MissingValue, "1:3".to_owned()));
+        assert_eq!(error("#inst \"2024-13\""), (
+            ErrorKind::InvalidTag { tag: "inst".to_owned(), message: "expected an RFC 3339 timestamp string".to_owned() },
+            "1:1".to_owned(),
+        ));
+        assert_eq!(error("[1 'a]"), (unsupported.clone(), "1:4".to_owned()));
+        assert_eq!(error("@a"), (unsupported.clone(), "1:1".to_owned()));
+        assert_eq!(error("#::{:a 1}"), (unsupported, "1:1".to_owned()));
+        assert_eq!(error("::a"), (ErrorKind::Syntax(TokenError::InvalidKeyword), "1:1".to_owned()));
+        assert_eq!(error("[1.2.3]"), (ErrorKind::Syntax(TokenError::InvalidNumber), "1:2".to_owned()));
+        assert_eq!(error("1/0"), (ErrorKind::InvalidNumber(NumberError::DivideByZero), "1:1".to_owned()));
+        assert_eq!(error("\"a\\qb\""), (ErrorKind::InvalidString(StringErrorKind::UnsupportedEscape('q')), "1:3".to_owned()));
+        assert_eq!(error("\\foo"), (ErrorKind::Syntax(TokenError::InvalidCharacter), "1:1".to_owned()));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let nested = |open: &str, close: &str, depth: usize| format!("{}1{}", open.repeat(depth), close.repeat(depth));
+
+        let value = parse(&nested("[", "]", MAX_DEPTH - 1)).unwrap();
+        assert_eq!(value.to_string(), nested("[", "]", MAX_DEPTH - 1));
+        assert_eq!(error(&nested("[", "]", MAX_DEPTH)), (ErrorKind::TooDeep, format!("1:{}", MAX_DEPTH + 1)));
+        assert_eq!(error(&nested("{:a ", "}", MAX_DEPTH)).0, ErrorKind::TooDeep);
+
+        for source in [
+            nested("[", "]", 100_000),
+            nested("(", "", 100_000),
+            nested("#{", "}", 100_000),
+            nested("#a ", "", 100_000),
+            format!("[{} 1]", nested("#_ ", "", 100_000)),
+            format!("[#_ {} 1]", nested("[", "]", 100_000)),
+        ] {
+            assert_eq!(parse(&source).unwrap_err().kind, ErrorKind::TooDeep);
+        }
+    }
+
+    #[test]
+    fn display_reads_back() {
+        let source = r#"{:s "q\"\\\t" :c [\a \space \u0001] :f [1.0 -0.5 1e100 ##Inf] :n [1N 2M 1/2] :l (a/b nil false)}"#;
+        let value = parse(source).unwrap();
+        assert_eq!(parse(&value.to_string()), Ok(value));
+    }
+
+    #[test]
+    fn pretty() {
+        let value = parse("{:name \"edn\" :deps {a/b {:version \"1.0\"} c/d {:version \"2.0\"}} :ids [1 2 3 4 5 6 7 8 9]}").unwrap();
+        let pretty = value.to_pretty_string(24);
+        assert_eq!(pretty, concat!(
+            "{:deps {a/b {:version\n",
+            "               \"1.0\"}\n",
+            "        c/d {:version\n",
+            "               \"2.0\"}}\n",
+            " :ids [1 2 3 4 5 6 7 8\n",
+            "       9]\n",
+            " :name \"edn\"}",
+        ));
+        assert_eq!(parse(&pretty), Ok(value.clone()));
+        assert_eq!(value.to_pretty_string(200), value.to_string().replace(", ", " "));
+    }
+}
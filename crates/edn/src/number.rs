@@ -0,0 +1,249 @@
+//! Arithmetic on the decimal digits of `Value::BigInt`, `Value::Ratio` and `Value::BigDecimal`,
+//! enough to compare them by value and to reduce ratios.
+//!
+//! Magnitudes are ASCII decimal digits without a sign and without leading zeros, `0` is `"0"`.
+
+use std::cmp::Ordering;
+
+fn split_sign(digits: &str) -> (bool, &str) {
+    match digits.strip_prefix('-') {
+        Some(magnitude) => (magnitude != "0", magnitude),
+        None => (false, digits),
+    }
+}
+
+fn trim(mut digits: Vec<u8>) -> String {
+    let zeros = digits.iter().take_while(|&&digit| digit == b'0').count();
+    digits.drain(..zeros.min(digits.len() - 1));
+    String::from_utf8(digits).expect("decimal digits are ASCII")
+}
+
+fn cmp_magnitudes(a: &str, b: &str) -> Ordering {
+    (a.len(), a).cmp(&(b.len(), b))
+}
+
+/// `a - b`, where `a >= b`
+fn sub(a: &str, b: &str) -> String {
+    let mut out = a.as_bytes().to_vec();
+    let mut borrow = 0;
+    for (i, digit) in out.iter_mut().rev().enumerate() {
+        let subtrahend = b.len().checked_sub(i + 1).map_or(0, |j| b.as_bytes()[j] - b'0') + borrow;
+        borrow = u8::from(*digit - b'0' < subtrahend);
+        *digit = *digit + 10 * borrow - subtrahend;
+    }
+    trim(out)
+}
+
+fn mul(a: &str, b: &str) -> String {
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.bytes().rev().enumerate() {
+        for (j, y) in b.bytes().rev().enumerate() {
+            out[i + j] += u32::from(x - b'0') * u32::from(y - b'0');
+            out[i + j + 1] += out[i + j] / 10;
+            out[i + j] %= 10;
+        }
+    }
+    trim(out.iter().rev().map(|&digit| b'0' + digit as u8).collect())
+}
+
+/// Quotient and remainder of long division, `b` is not zero
+fn div_rem(a: &str, b: &str) -> (String, String) {
+    let mut quotient = Vec::with_capacity(a.len());
+    let mut remainder = "0".to_owned();
+    for digit in a.chars() {
+        remainder.push(digit);
+        remainder = trim(remainder.into_bytes());
+        let mut count = b'0';
+        while cmp_magnitudes(&remainder, b) != Ordering::Less {
+            remainder = sub(&remainder, b);
+            count += 1;
+        }
+        quotient.push(count);
+    }
+    (trim(quotient), remainder)
+}
+
+fn gcd(mut a: String, mut b: String) -> String {
+    while b != "0" {
+        let (_, remainder) = div_rem(&a, &b);
+        a = std::mem::replace(&mut b, remainder);
+    }
+    a
+}
+
+/// Compares the decimal digits of two integers, each with a `-` sign if negative
+pub(crate) fn cmp_integers(a: &str, b: &str) -> Ordering {
+    match (split_sign(a), split_sign(b)) {
+        ((true, a), (true, b)) => cmp_magnitudes(b, a),
+        ((true, _), (false, _)) => Ordering::Less,
+        ((false, _), (true, _)) => Ordering::Greater,
+        ((false, a), (false, b)) => cmp_magnitudes(a, b),
+    }
+}
+
+/// Compares `a / b` with `c / d`, where the denominators are positive
+pub(crate) fn cmp_ratios((a, b): (&str, &str), (c, d): (&str, &str)) -> Ordering {
+    let ((a_negative, a), (c_negative, c)) = (split_sign(a), split_sign(c));
+    let sign = |negative, magnitude| match (negative, magnitude) {
+        (true, _) => Ordering::Less,
+        (false, "0") => Ordering::Equal,
+        (false, _) => Ordering::Greater,
+    };
+    match sign(a_negative, a).cmp(&sign(c_negative, c)) {
+        Ordering::Equal if a_negative => cmp_magnitudes(&mul(c, b), &mul(a, d)),
+        Ordering::Equal => cmp_magnitudes(&mul(a, d), &mul(c, b)),
+        ordering => ordering,
+    }
+}
+
+/// The ratio in lowest terms, the numerator keeps the sign
+pub(crate) fn reduce(numerator: &str, denominator: &str) -> (String, String) {
+    let (negative, magnitude) = split_sign(numerator);
+    let divisor = gcd(magnitude.to_owned(), denominator.to_owned());
+    let (numerator, _) = div_rem(magnitude, &divisor);
+    let (denominator, _) = div_rem(denominator, &divisor);
+    (if negative { format!("-{}", numerator) } else { numerator }, denominator)
+}
+
+/// Digits of the exact value of a finite float, e.g. `0.1000000000000000055511151231257827021181583404541015625`
+/// for `0.1`. The smallest float has 1074 fractional digits
+fn exact_decimal(n: f64) -> String {
+    let decimal = format!("{:.1074}", n);
+    decimal.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+/// The float with exactly the value of the `Value::BigDecimal` digits, if there is one
+pub(crate) fn decimal_to_f64(decimal: &str) -> Option<f64> {
+    let n: f64 = decimal.parse().ok()?;
+    (n.is_finite() && Decimal::new(&exact_decimal(n)) == Decimal::new(decimal)).then_some(n)
+}
+
+/// The float with exactly the value of the ratio, if there is one
+pub(crate) fn ratio_to_f64(numerator: &str, denominator: &str) -> Option<f64> {
+    let n = numerator.parse::<f64>().ok()? / denominator.parse::<f64>().ok()?;
+    if !n.is_finite() {
+        return None;
+    }
+    // The float as a ratio with a power of 10 as the denominator
+    let exact = exact_decimal(n);
+    let (int, frac) = exact.split_once('.').unwrap_or((&exact, ""));
+    let (negative, int) = split_sign(int);
+    let digits = trim(format!("{}{}", int, frac).into_bytes());
+    let float_numerator = if negative { format!("-{}", digits) } else { digits };
+    let float_denominator = format!("1{}", "0".repeat(frac.len()));
+    (cmp_ratios((numerator, denominator), (&float_numerator, &float_denominator)) == Ordering::Equal).then_some(n)
+}
+
+/// A decimal as `0.digits * 10^exponent` without leading or trailing zeros in the digits,
+/// so decimals of the same value have the same parts. Zero has no digits and is not negative
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Decimal {
+    negative: bool,
+    exponent: i64,
+    digits: String,
+}
+
+impl Decimal {
+    /// Parts of the digits of a `Value::BigDecimal`, e.g. `-1.50e3`
+    pub(crate) fn new(decimal: &str) -> Self {
+        let (negative, decimal) = split_sign(decimal.strip_prefix('+').unwrap_or(decimal));
+        let (mantissa, exponent) = decimal.split_once(['e', 'E']).unwrap_or((decimal, "0"));
+        // An exponent out of range is far from those of the digits
+        let exponent = exponent.parse::<i64>().unwrap_or(if exponent.starts_with('-') { i64::MIN } else { i64::MAX });
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+        let digits = format!("{}{}", int, frac);
+        let leading = digits.bytes().take_while(|&digit| digit == b'0').count();
+        let digits = digits[leading..].trim_end_matches('0');
+        if digits.is_empty() {
+            return Decimal { negative: false, exponent: 0, digits: String::new() };
+        }
+        let exponent = exponent.saturating_add(int.len() as i64 - leading as i64);
+        Decimal { negative, exponent, digits: digits.to_owned() }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sign = |decimal: &Decimal| match (decimal.negative, decimal.digits.is_empty()) {
+            (true, _) => Ordering::Less,
+            (false, true) => Ordering::Equal,
+            (false, false) => Ordering::Greater,
+        };
+        // The longer digits of the same exponent are greater when they share a prefix
+        let magnitude = || (self.exponent, &self.digits).cmp(&(other.exponent, &other.digits));
+        match sign(self).cmp(&sign(other)) {
+            Ordering::Equal if sign(self) == Ordering::Equal => Ordering::Equal,
+            Ordering::Equal if self.negative => magnitude().reverse(),
+            Ordering::Equal => magnitude(),
+            ordering => ordering,
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::{cmp_ratios, decimal_to_f64, div_rem, gcd, mul, ratio_to_f64, reduce, sub, Decimal};
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(sub("1000", "1"), "999");
+        assert_eq!(sub("42", "42"), "0");
+        assert_eq!(mul("99", "99"), "9801");
+        assert_eq!(mul("0", "123"), "0");
+        assert_eq!(mul("123456789123456789", "987654321987654321"), "121932631356500531347203169112635269");
+        assert_eq!(div_rem("1000", "7"), ("142".to_owned(), "6".to_owned()));
+        assert_eq!(div_rem("5", "10"), ("0".to_owned(), "5".to_owned()));
+        assert_eq!(gcd("0".to_owned(), "5".to_owned()), "5");
+        assert_eq!(gcd("121932631356500531347203169112635269".to_owned(), "987654321987654321".to_owned()), "987654321987654321");
+    }
+
+    #[test]
+    fn ratios() {
+        assert_eq!(reduce("-6", "4"), ("-3".to_owned(), "2".to_owned()));
+        assert_eq!(reduce("0", "5"), ("0".to_owned(), "1".to_owned()));
+        assert_eq!(cmp_ratios(("1", "2"), ("1", "3")), Ordering::Greater);
+        assert_eq!(cmp_ratios(("-1", "2"), ("-1", "3")), Ordering::Less);
+        assert_eq!(cmp_ratios(("2", "4"), ("1", "2")), Ordering::Equal);
+        assert_eq!(cmp_ratios(("0", "4"), ("-0", "1")), Ordering::Equal);
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(decimal_to_f64("1.50"), Some(1.5));
+        assert_eq!(decimal_to_f64("-0.0"), Some(-0.0));
+        assert_eq!(decimal_to_f64("1e3"), Some(1000.0));
+        assert_eq!(decimal_to_f64("0.1"), None);
+        assert_eq!(decimal_to_f64("1e400"), None);
+        assert_eq!(decimal_to_f64("9007199254740993"), None);
+        assert_eq!(decimal_to_f64("0.1000000000000000055511151231257827021181583404541015625"), Some(0.1));
+        assert_eq!(ratio_to_f64("3", "2"), Some(1.5));
+        assert_eq!(ratio_to_f64("-6", "4"), Some(-1.5));
+        assert_eq!(ratio_to_f64("0", "5"), Some(0.0));
+        assert_eq!(ratio_to_f64("1", "3"), None);
+        assert_eq!(ratio_to_f64("1", "10"), None);
+        assert_eq!(ratio_to_f64("9007199254740993", "2"), None);
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(Decimal::new("1.0"), Decimal::new("1.00"));
+        assert_eq!(Decimal::new("1e1"), Decimal::new("10"));
+        assert_eq!(Decimal::new("0.0"), Decimal::new("-0"));
+        assert_eq!(Decimal::new("-0.0150E+2"), Decimal::new("-1.5"));
+        assert!(Decimal::new("10") > Decimal::new("9"));
+        assert!(Decimal::new("-10") < Decimal::new("-9"));
+        assert!(Decimal::new("0.5") < Decimal::new("1"));
+        assert!(Decimal::new("1.25") > Decimal::new("1.2"));
+        assert!(Decimal::new("-0.001") < Decimal::new("0"));
+        assert!(Decimal::new("1e99999999999999999999") > Decimal::new("1e100"));
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use tokenizer::dialect::Dialect;
+use tokenizer::error::ErrorKind as TokenError;
+use tokenizer::span::Position;
+use tokenizer::symbol::parse_symbol;
+use tokenizer::token::{Token, TokenType};
+
+use crate::error::{Error, ErrorKind};
+use crate::lexer::{self, Lexer};
+use crate::tags::{Inst, Uuid};
+use crate::value::{Keyword, Symbol, Value};
+
+/// Converts the value following a tag, or explains why it can't
+pub type TagHandler = Box<dyn Fn(Value) -> Result<Value, String>>;
+
+/// Reads EDN with user-registered tag handlers.
+///
+/// `#inst` and `#uuid` are built in. Values with other tags which have no handler
+/// are kept as `Value::Tagged`.
+#[derive(Default)]
+pub struct Reader {
+    handlers: HashMap<String, TagHandler>,
+}
+
+impl Reader {
+    pub fn new() -> Self {
+        Reader::default()
+    }
+
+    /// Handles values with the tag, e.g. `my/point` for `#my/point [1 2]`.
+    /// Replaces the built-in handling of `inst` and `uuid`
+    pub fn with_tag(mut self, tag: &str, handler: impl Fn(Value) -> Result<Value, String> + 'static) -> Self {
+        self.handlers.insert(tag.to_owned(), Box::new(handler));
+        self
+    }
+
+    /// Reads the only value of the source
+    pub fn read(&self, source: &str) -> Result<Value, Error> {
+        let mut lexer = Lexer::new(source);
+        let Some(token) = lexer.next()? else {
+            return Err(Error::new(ErrorKind::MissingValue, lexer.end()));
+        };
+        let value = self.value(&mut lexer, token)?;
+        match lexer.next()? {
+            Some(token) => Err(Error::new(ErrorKind::ExpectedEnd, token.start())),
+            None => Ok(value),
+        }
+    }
+
+    /// Reads all values of the source, e.g. of a file with one value per line
+    pub fn read_all(&self, source: &str) -> Result<Vec<Value>, Error> {
+        let mut lexer = Lexer::new(source);
+        let mut values = Vec::new();
+        while let Some(token) = lexer.next()? {
+            values.push(self.value(&mut lexer, token)?);
+        }
+        Ok(values)
+    }
+
+    /// Reads the value starting with `token`
+    pub(crate) fn value(&self, lexer: &mut Lexer, token: Token) -> Result<Value, Error> {
+        lexer.enter(token)?;
+        let value = self.nested(lexer, token)?;
+        lexer.leave();
+        Ok(value)
+    }
+
+    /// `value` once the depth is checked
+    fn nested(&self, lexer: &mut Lexer, token: Token) -> Result<Value, Error> {
+        match token.kind() {
+            TokenType::LParen => Ok(Value::List(self.seq(lexer, token)?.into_iter().map(|(_, value)| value).collect())),
+            TokenType::LBracket => Ok(Value::Vector(self.seq(lexer, token)?.into_iter().map(|(_, value)| value).collect())),
+            TokenType::LBrace => map(self.seq(lexer, token)?, token).map(Value::Map),
+            TokenType::LSet => {
+                let mut set = BTreeSet::new();
+                for (position, value) in self.seq(lexer, token)? {
+                    if set.contains(&value) {
+                        return Err(Error::new(ErrorKind::DuplicateElement(value.to_string()), position));
+                    }
+                    set.insert(value);
+                }
+                Ok(Value::Set(set))
+            },
+            TokenType::Tag => self.tagged(lexer, token),
+            TokenType::NamespacedMap => self.namespaced_map(lexer, token),
+            _ if token.is_right() => Err(lexer::unexpected(token, None)),
+            _ => lexer::atom(token),
+        }
+    }
+
+    /// Values up to the closing delimiter, with their positions
+    fn seq(&self, lexer: &mut Lexer, open: Token) -> Result<Vec<(Position, Value)>, Error> {
+        let mut values = Vec::new();
+        loop {
+            match lexer.next()? {
+                Some(close) if lexer::closes(open, close) => return Ok(values),
+                Some(close) if close.is_right() => return Err(lexer::unexpected(close, Some(open))),
+                Some(token) => values.push((token.start(), self.value(lexer, token)?)),
+                None => return Err(lexer::unclosed(open)),
+            }
+        }
+    }
+
+    fn tagged(&self, lexer: &mut Lexer, tag: Token) -> Result<Value, Error> {
+        let name = &tag.value()[1..];
+        let symbol = parse_symbol(name).map_err(|_| Error::new(ErrorKind::Syntax(TokenError::InvalidSymbol), tag.start()))?;
+        let token = lexer.next_value(tag)?;
+        let value = self.value(lexer, token)?;
+
+        let invalid = |message: String| Error::new(ErrorKind::InvalidTag { tag: name.to_owned(), message }, tag.start());
+        if let Some(handler) = self.handlers.get(name) {
+            return handler(value).map_err(invalid);
+        }
+        match name {
+            "inst" => value
+                .as_str()
+                .and_then(Inst::parse)
+                .map(Value::Inst)
+                .ok_or_else(|| invalid("expected an RFC 3339 timestamp string".to_owned())),
+            "uuid" => value
+                .as_str()
+                .and_then(Uuid::parse)
+                .map(Value::Uuid)
+                .ok_or_else(|| invalid("expected a UUID string".to_owned())),
+            _ => Ok(Value::Tagged(Symbol::from(symbol), Box::new(value))),
+        }
+    }
+
+    /// `#:ns{:a 1}` is `{:ns/a 1}`, keys with the `_` namespace lose it
+    fn namespaced_map(&self, lexer: &mut Lexer, prefix: Token) -> Result<Value, Error> {
+        let Some(namespace) = prefix.value().strip_prefix("#:").filter(|ns| !ns.starts_with(':')) else {
+            let error = TokenError::UnsupportedSyntax(Dialect::Edn);
+            return Err(Error::new(ErrorKind::Syntax(error), prefix.start()));
+        };
+        let open = lexer.next_value(prefix)?;
+        if open.kind() != TokenType::LBrace {
+            return Err(Error::new(ErrorKind::Syntax(TokenError::InvalidNamespacedMap), prefix.start()));
+        }
+
+        let mut entries = self.seq(lexer, open)?;
+        for (_, key) in entries.iter_mut().step_by(2) {
+            match key {
+                Value::Keyword(Keyword { namespace: key_namespace, .. })
+                | Value::Symbol(Symbol { namespace: key_namespace, .. }) => match key_namespace.as_deref() {
+                    None => *key_namespace = Some(namespace.to_owned()),
+                    Some("_") => *key_namespace = None,
+                    Some(_) => (),
+                },
+                _ => (),
+            }
+        }
+        map(entries, open).map(Value::Map)
+    }
+}
+
+fn map(entries: Vec<(Position, Value)>, open: Token) -> Result<BTreeMap<Value, Value>, Error> {
+    if !entries.len().is_multiple_of(2) {
+        return Err(Error::new(ErrorKind::OddMap, open.start()));
+    }
+
+    let mut map = BTreeMap::new();
+    let mut entries = entries.into_iter();
+    while let (Some((position, key)), Some((_, value))) = (entries.next(), entries.next()) {
+        if map.contains_key(&key) {
+            return Err(Error::new(ErrorKind::DuplicateKey(key.to_string()), position));
+        }
+        map.insert(key, value);
+    }
+    Ok(map)
+}
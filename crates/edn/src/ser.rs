@@ -0,0 +1,503 @@
+use std::fmt::Write as _;
+
+use serde::ser::{self, Serialize};
+use tokenizer::span::Position;
+use tokenizer::symbol::parse_keyword;
+use tokenizer::token::TokenType;
+use tokenizer::Tokenizer;
+
+use crate::error::{Error, ErrorKind};
+use crate::value::{layout, write_char, write_float, write_string};
+
+/// Writes EDN on a single line, the same as `Value` is displayed.
+///
+/// Structs are maps with keyword keys in the order of their fields, unit variants are
+/// keywords, and variants with data are maps with a single entry, e.g. `{:circle 1.5}`.
+/// `None` and `()` are `nil`. Maps are sorted by key as `Value` sorts them, so the
+/// output doesn't depend on the iteration order of e.g. a `HashMap`.
+#[derive(Default)]
+pub struct Serializer {
+    out: String,
+}
+
+/// Serializes the value as EDN
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    let mut serializer = Serializer::new();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_string())
+}
+
+/// Serializes the value as EDN broken into lines to fit in `width` columns where possible
+pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T, width: usize) -> Result<String, Error> {
+    Ok(layout(&to_string(value)?, width))
+}
+
+impl Serializer {
+    pub fn new() -> Self {
+        Serializer::default()
+    }
+
+    /// The EDN written by `value.serialize(&mut serializer)`
+    pub fn into_string(self) -> String {
+        self.out
+    }
+
+    /// Writes the field or variant name as a keyword, which must read back as one
+    fn keyword(&mut self, name: &str) -> Result<(), Error> {
+        let keyword = format!(":{}", name);
+        let mut tokens = Tokenizer::from(keyword.as_str());
+        let single = tokens.next().is_some_and(|token| token.kind() == TokenType::Keyword && token.value() == keyword)
+            && tokens.next().is_none();
+        if !single || parse_keyword(&keyword).is_err() {
+            return Err(Error::new(ErrorKind::InvalidKeyword(name.to_owned()), Position::new(0, 0, 0)));
+        }
+        self.out.push_str(&keyword);
+        Ok(())
+    }
+
+    /// Opens `{:variant ` of a variant with data
+    fn variant(&mut self, variant: &str) -> Result<(), Error> {
+        self.out.push('{');
+        self.keyword(variant)?;
+        self.out.push(' ');
+        Ok(())
+    }
+}
+
+/// A sequence or a map being written
+pub struct Compound<'a> {
+    ser: &'a mut Serializer,
+    first: bool,
+    /// Closing delimiters
+    close: &'static str,
+    /// Keys and values of a map, written sorted by key at the end
+    entries: Vec<(String, String)>,
+}
+
+impl Compound<'_> {
+    fn separator(&mut self, separator: &str) {
+        if !self.first {
+            self.ser.out.push_str(separator);
+        }
+        self.first = false;
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.ser.out.push_str(self.close);
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.out.push_str(if v { "true" } else { "false" });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        let _ = write!(self.out, "{}", v);
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        let _ = write!(self.out, "{}", v);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        let _ = write!(self.out, "{}", v);
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        let _ = write!(self.out, "{}", v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        // Widening would print the binary error, e.g. 0.10000000149011612 for 0.1
+        if v.is_finite() {
+            let _ = write!(self.out, "{:?}", v);
+            Ok(())
+        } else {
+            self.serialize_f64(v as f64)
+        }
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        let _ = write_float(&mut self.out, v);
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        let _ = write_char(&mut self.out, v);
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        let _ = write_string(&mut self.out, v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        v.serialize(self)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.out.push_str("nil");
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<(), Error> {
+        self.keyword(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.variant(variant)?;
+        value.serialize(&mut *self)?;
+        self.out.push('}');
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+        self.out.push('[');
+        Ok(Compound { ser: self, first: true, close: "]", entries: Vec::new() })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.variant(variant)?;
+        self.out.push('[');
+        Ok(Compound { ser: self, first: true, close: "]}", entries: Vec::new() })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+        self.out.push('{');
+        Ok(Compound { ser: self, first: true, close: "}", entries: Vec::new() })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.variant(variant)?;
+        self.out.push('{');
+        Ok(Compound { ser: self, first: true, close: "}}", entries: Vec::new() })
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.separator(" ");
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.entries.push((to_string(key)?, String::new()));
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let value = to_string(value)?;
+        if let Some(entry) = self.entries.last_mut() {
+            entry.1 = value;
+        }
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), Error> {
+        // The keys are EDN written by the serializer, so they read back
+        let mut entries = std::mem::take(&mut self.entries);
+        entries.sort_by_cached_key(|(key, _)| crate::parse(key).ok());
+        for (key, value) in entries {
+            self.separator(", ");
+            self.ser.out.push_str(&key);
+            self.ser.out.push(' ');
+            self.ser.out.push_str(&value);
+        }
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.separator(", ");
+        self.ser.keyword(key)?;
+        self.ser.out.push(' ');
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{to_string, to_string_pretty, Serializer};
+    use crate::de::from_str;
+    use crate::error::ErrorKind;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Shape {
+        Circle(f64),
+        Rect { width: u32, height: u32 },
+        Line(i32, i32),
+        Point,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Document {
+        title: String,
+        tags: Vec<String>,
+        max_size: Option<u64>,
+        ratio: f32,
+        initial: char,
+        shapes: Vec<Shape>,
+        counts: BTreeMap<String, i64>,
+        unit: (),
+    }
+
+    #[test]
+    fn document() {
+        let document = Document {
+            title: "Say \"hi\"\n".to_owned(),
+            tags: vec!["a".to_owned(), "b".to_owned()],
+            max_size: None,
+            ratio: 0.1,
+            initial: ' ',
+            shapes: vec![Shape::Circle(1.0), Shape::Rect { width: 2, height: 3 }, Shape::Line(-1, 1), Shape::Point],
+            counts: BTreeMap::from([("x".to_owned(), 1)]),
+            unit: (),
+        };
+        let edn = to_string(&document).unwrap();
+        assert_eq!(edn, concat!(
+            r#"{:title "Say \"hi\"\n", :tags ["a" "b"], :max-size nil, :ratio 0.1, :initial \space, "#,
+            r#":shapes [{:circle 1.0} {:rect {:width 2, :height 3}} {:line [-1 1]} :point], :counts {"x" 1}, :unit nil}"#,
+        ));
+        assert_eq!(from_str::<Document>(&edn), Ok(document));
+    }
+
+    #[test]
+    fn pretty() {
+        let document = Document {
+            title: "Shapes".to_owned(),
+            tags: vec!["a".to_owned(), "b".to_owned()],
+            max_size: Some(10),
+            ratio: 0.5,
+            initial: 's',
+            shapes: vec![Shape::Circle(1.0), Shape::Rect { width: 2, height: 3 }],
+            counts: BTreeMap::new(),
+            unit: (),
+        };
+        assert_eq!(to_string_pretty(&document, 40).unwrap(), concat!(
+            "{:title \"Shapes\"\n",
+            " :tags [\"a\" \"b\"]\n",
+            " :max-size 10\n",
+            " :ratio 0.5\n",
+            " :initial \\s\n",
+            " :shapes [{:circle 1.0}\n",
+            "          {:rect {:width 2 :height 3}}]\n",
+            " :counts {}\n",
+            " :unit nil}",
+        ));
+        assert_eq!(to_string_pretty(&document.tags, 40).unwrap(), "[\"a\" \"b\"]");
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(to_string(&f64::NAN).unwrap(), "##NaN");
+        assert_eq!(to_string(&-2.0f64).unwrap(), "-2.0");
+        assert_eq!(to_string(&u64::MAX).unwrap(), "18446744073709551615");
+        assert_eq!(to_string(&'\u{1}').unwrap(), "\\u0001");
+        assert_eq!(to_string(&Some("x")).unwrap(), "\"x\"");
+        assert_eq!(to_string(&[1u8, 2]).unwrap(), "[1 2]");
+
+        let mut serializer = Serializer::new();
+        (1, "a").serialize(&mut serializer).unwrap();
+        assert_eq!(serializer.into_string(), "[1 \"a\"]");
+    }
+
+    #[test]
+    fn sorted_maps() {
+        let map: HashMap<&str, i32> = HashMap::from([("z", 1), ("b", 2), ("m", 3), ("a", 4)]);
+        assert_eq!(to_string(&map).unwrap(), r#"{"a" 4, "b" 2, "m" 3, "z" 1}"#);
+
+        let map: HashMap<i64, Vec<i64>> = HashMap::from([(10, vec![]), (9, vec![1]), (-1, vec![2])]);
+        assert_eq!(to_string(&map).unwrap(), "{-1 [2], 9 [1], 10 []}");
+        assert_eq!(to_string(&map).unwrap(), crate::parse(&to_string(&map).unwrap()).unwrap().to_string());
+    }
+
+    #[test]
+    fn invalid_keywords() {
+        #[derive(Serialize)]
+        struct Renamed {
+            #[serde(rename = "a b")]
+            a: i32,
+            y: (),
+        }
+
+        #[derive(Serialize)]
+        enum Variant {
+            #[serde(rename = "1/x")]
+            Unit,
+            #[serde(rename = "ok/")]
+            Data(i32),
+        }
+
+        let error = to_string(&Renamed { a: 1, y: () }).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidKeyword("a b".to_owned()));
+        assert_eq!(error.to_string(), "`a b` can't be written as a keyword");
+        assert_eq!(to_string(&Variant::Unit).unwrap_err().kind, ErrorKind::InvalidKeyword("1/x".to_owned()));
+        assert_eq!(to_string(&[Variant::Data(1)]).unwrap_err().kind, ErrorKind::InvalidKeyword("ok/".to_owned()));
+    }
+}
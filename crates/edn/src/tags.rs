@@ -0,0 +1,215 @@
+use std::fmt;
+
+/// The `#inst` timestamp as written, in RFC 3339 format.
+///
+/// Omitted parts default to the start of the period, e.g. `#inst "2024"` is
+/// the 1st of January. Values are ordered by the fields, not by the instant.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct Inst {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+    /// Offset from UTC in minutes
+    pub offset: i16,
+}
+
+struct Scanner<'s> {
+    bytes: &'s [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn eat(&mut self, b: u8) -> bool {
+        let found = self.bytes.get(self.pos) == Some(&b);
+        self.pos += found as usize;
+        found
+    }
+
+    fn digits(&mut self, n: usize) -> Option<u32> {
+        let digits = self.bytes.get(self.pos..self.pos + n)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.pos += n;
+        Some(digits.iter().fold(0, |acc, d| acc * 10 + (d - b'0') as u32))
+    }
+
+    /// Fraction of a second in nanoseconds, digits past nanoseconds are dropped
+    fn fraction(&mut self) -> Option<u32> {
+        let len = self.bytes[self.pos..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        let digits = &self.bytes[self.pos..self.pos + len.min(9)];
+        self.pos += len;
+        let value = digits.iter().fold(0, |acc, d| acc * 10 + (d - b'0') as u32);
+        Some(value * 10u32.pow(9 - digits.len() as u32))
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Inst {
+    /// Parses `yyyy-mm-ddThh:mm:ss.fff+hh:mm`, where any suffix of the date and time
+    /// may be omitted, as the Clojure reader does
+    pub fn parse(s: &str) -> Option<Inst> {
+        let mut scanner = Scanner { bytes: s.as_bytes(), pos: 0 };
+        let mut inst = Inst {
+            year: scanner.digits(4)? as u16,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            nanosecond: 0,
+            offset: 0,
+        };
+
+        if scanner.eat(b'-') {
+            inst.month = scanner.digits(2)? as u8;
+            if scanner.eat(b'-') {
+                inst.day = scanner.digits(2)? as u8;
+                if scanner.eat(b'T') {
+                    inst.hour = scanner.digits(2)? as u8;
+                    if scanner.eat(b':') {
+                        inst.minute = scanner.digits(2)? as u8;
+                        if scanner.eat(b':') {
+                            inst.second = scanner.digits(2)? as u8;
+                            if scanner.eat(b'.') {
+                                inst.nanosecond = scanner.fraction()?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if !scanner.eat(b'Z') {
+            let sign = if scanner.eat(b'+') {
+                Some(1)
+            } else if scanner.eat(b'-') {
+                Some(-1)
+            } else {
+                None
+            };
+            if let Some(sign) = sign {
+                let hours = scanner.digits(2)?;
+                if !scanner.eat(b':') {
+                    return None;
+                }
+                let minutes = scanner.digits(2)?;
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                inst.offset = sign * (hours * 60 + minutes) as i16;
+            }
+        }
+
+        let valid = scanner.pos == s.len()
+            && (1..=12).contains(&inst.month)
+            && (1..=days_in_month(inst.year, inst.month)).contains(&inst.day)
+            && inst.hour < 24
+            && inst.minute < 60
+            // A leap second
+            && (inst.second < 60 || inst.second == 60 && inst.minute == 59);
+        valid.then_some(inst)
+    }
+}
+
+impl fmt::Display for Inst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.nanosecond.is_multiple_of(1_000_000) {
+            write!(f, ".{:03}", self.nanosecond / 1_000_000)?;
+        } else {
+            write!(f, ".{:09}", self.nanosecond)?;
+        }
+        // Clojure prints UTC as `-00:00`
+        let sign = if self.offset > 0 { '+' } else { '-' };
+        let offset = self.offset.unsigned_abs();
+        write!(f, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+    }
+}
+
+/// The `#uuid` value
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct Uuid(pub [u8; 16]);
+
+impl Uuid {
+    /// Parses the canonical `8-4-4-4-12` hex digit form, in any case
+    pub fn parse(s: &str) -> Option<Uuid> {
+        let bytes = s.as_bytes();
+        if bytes.len() != 36 || [8, 13, 18, 23].iter().any(|&i| bytes[i] != b'-') {
+            return None;
+        }
+
+        let mut digits = bytes.iter().filter(|&&b| b != b'-');
+        let mut uuid = [0; 16];
+        for byte in &mut uuid {
+            let high = (*digits.next()? as char).to_digit(16)?;
+            let low = (*digits.next()? as char).to_digit(16)?;
+            *byte = (high * 16 + low) as u8;
+        }
+        Some(Uuid(uuid))
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Inst, Uuid};
+
+    #[test]
+    fn inst() {
+        let inst = Inst::parse("2024-02-29T13:45:30.5+03:30").unwrap();
+        assert_eq!((inst.year, inst.month, inst.day, inst.hour, inst.minute, inst.second), (2024, 2, 29, 13, 45, 30));
+        assert_eq!((inst.nanosecond, inst.offset), (500_000_000, 210));
+        assert_eq!(inst.to_string(), "2024-02-29T13:45:30.500+03:30");
+
+        assert_eq!(Inst::parse("2024").unwrap().to_string(), "2024-01-01T00:00:00.000-00:00");
+        assert_eq!(Inst::parse("2024-01-01T00:00:00.123456789123Z").unwrap().to_string(), "2024-01-01T00:00:00.123456789-00:00");
+        assert_eq!(Inst::parse("1999-12-31T23:59:60-01:00").unwrap().offset, -60);
+
+        for invalid in ["", "24", "2023-02-29", "2024-13", "2024-01-01T24", "2024-01-01T", "2024-01-01 10:00", "2024Z1", "2024+1:00"] {
+            assert_eq!(Inst::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn uuid() {
+        let uuid = Uuid::parse("F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6").unwrap();
+        assert_eq!(uuid.0[0], 0xf8);
+        assert_eq!(uuid.to_string(), "f81d4fae-7dec-11d0-a765-00a0c91e6bf6");
+
+        for invalid in ["", "f81d4fae7dec11d0a76500a0c91e6bf6", "f81d4fae-7dec-11d0-a765-00a0c91e6bfg", "f81d4fae-7dec-11d0-a765+00a0c91e6bf6"] {
+            assert_eq!(Uuid::parse(invalid), None, "{}", invalid);
+        }
+    }
+}
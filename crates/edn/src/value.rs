@@ -0,0 +1,313 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use tokenizer::dialect::Dialect;
+use tokenizer::symbol;
+
+use crate::number::{cmp_integers, cmp_ratios, reduce, Decimal};
+use crate::tags::{Inst, Uuid};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct Symbol {
+    pub namespace: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct Keyword {
+    pub namespace: Option<String>,
+    pub name: String,
+}
+
+impl Symbol {
+    pub fn new(namespace: Option<&str>, name: &str) -> Self {
+        Symbol { namespace: namespace.map(str::to_owned), name: name.to_owned() }
+    }
+}
+
+impl Keyword {
+    pub fn new(namespace: Option<&str>, name: &str) -> Self {
+        Keyword { namespace: namespace.map(str::to_owned), name: name.to_owned() }
+    }
+}
+
+impl From<symbol::Symbol<'_>> for Symbol {
+    fn from(symbol: symbol::Symbol<'_>) -> Self {
+        Symbol::new(symbol.namespace, symbol.name)
+    }
+}
+
+impl From<symbol::Keyword<'_>> for Keyword {
+    fn from(keyword: symbol::Keyword<'_>) -> Self {
+        Keyword::new(keyword.namespace, keyword.name)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.namespace {
+            Some(namespace) => write!(f, "{}/{}", namespace, self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.namespace {
+            Some(namespace) => write!(f, ":{}/{}", namespace, self.name),
+            None => write!(f, ":{}", self.name),
+        }
+    }
+}
+
+/// An EDN value.
+///
+/// Values are totally ordered, so they can be map keys and set elements. Floats are
+/// compared with `f64::total_cmp`, so `##NaN` equals itself. Values of different variants
+/// are never equal, e.g. `1` and `1N` or `1` and `1.0`.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Integer(i64),
+    /// Decimal digits of an integer which doesn't fit into `i64` or has the `N` suffix,
+    /// with a `-` sign if negative and without leading zeros. Integers of any size are read
+    BigInt(String),
+    Float(f64),
+    /// Decimal digits without the `M` suffix, written as read. Compared by value,
+    /// so `1.0M` equals `1.00M`
+    BigDecimal(String),
+    /// Ratio in decimal digits like `BigInt`, with the sign on the numerator. Ratios are
+    /// reduced when read, and one with denominator 1 is read as an integer, e.g. `4/2` as `2`
+    Ratio { numerator: String, denominator: String },
+    String(String),
+    Char(char),
+    Keyword(Keyword),
+    Symbol(Symbol),
+    List(Vec<Value>),
+    Vector(Vec<Value>),
+    Map(BTreeMap<Value, Value>),
+    Set(BTreeSet<Value>),
+    /// `#inst`
+    Inst(Inst),
+    /// `#uuid`
+    Uuid(Uuid),
+    /// A value with a tag which has no handler
+    Tagged(Symbol, Box<Value>),
+}
+
+impl Value {
+    /// Order of the variants when values of different variants are compared
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Nil => 0,
+            Value::Bool(_) => 1,
+            Value::Integer(_) => 2,
+            Value::BigInt(_) => 3,
+            Value::Float(_) => 4,
+            Value::BigDecimal(_) => 5,
+            Value::Ratio { .. } => 6,
+            Value::String(_) => 7,
+            Value::Char(_) => 8,
+            Value::Keyword(_) => 9,
+            Value::Symbol(_) => 10,
+            Value::List(_) => 11,
+            Value::Vector(_) => 12,
+            Value::Map(_) => 13,
+            Value::Set(_) => 14,
+            Value::Inst(_) => 15,
+            Value::Uuid(_) => 16,
+            Value::Tagged(..) => 17,
+        }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Value::Nil)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Writes the value as EDN broken into lines to fit in `width` columns where possible,
+    /// with map entries on their own lines
+    pub fn to_pretty_string(&self, width: usize) -> String {
+        layout(&self.to_string(), width)
+    }
+
+    /// Looks up a map entry by a keyword without namespace, e.g. `get("port")` for `{:port 8080}`
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Map(map) => map.get(&Value::Keyword(Keyword::new(None, name))),
+            _ => None,
+        }
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => Ordering::Equal,
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+            (Value::BigInt(a), Value::BigInt(b)) => cmp_integers(a, b),
+            (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+            (Value::BigDecimal(a), Value::BigDecimal(b)) => Decimal::new(a).cmp(&Decimal::new(b)),
+            (
+                Value::Ratio { numerator: a, denominator: b },
+                Value::Ratio { numerator: c, denominator: d },
+            ) => cmp_ratios((a, b), (c, d)),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Char(a), Value::Char(b)) => a.cmp(b),
+            (Value::Keyword(a), Value::Keyword(b)) => a.cmp(b),
+            (Value::Symbol(a), Value::Symbol(b)) => a.cmp(b),
+            (Value::List(a), Value::List(b)) => a.cmp(b),
+            (Value::Vector(a), Value::Vector(b)) => a.cmp(b),
+            (Value::Map(a), Value::Map(b)) => a.cmp(b),
+            (Value::Set(a), Value::Set(b)) => a.cmp(b),
+            (Value::Inst(a), Value::Inst(b)) => a.cmp(b),
+            (Value::Uuid(a), Value::Uuid(b)) => a.cmp(b),
+            (Value::Tagged(a, x), Value::Tagged(b, y)) => (a, x).cmp(&(b, y)),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Value::Nil => (),
+            Value::Bool(b) => b.hash(state),
+            Value::Integer(n) => n.hash(state),
+            Value::BigInt(s) | Value::String(s) => s.hash(state),
+            Value::BigDecimal(s) => Decimal::new(s).hash(state),
+            // Equal by `total_cmp` means the same bits
+            Value::Float(n) => n.to_bits().hash(state),
+            Value::Ratio { numerator, denominator } => reduce(numerator, denominator).hash(state),
+            Value::Char(c) => c.hash(state),
+            Value::Keyword(keyword) => keyword.hash(state),
+            Value::Symbol(symbol) => symbol.hash(state),
+            Value::List(values) | Value::Vector(values) => values.hash(state),
+            Value::Map(map) => map.hash(state),
+            Value::Set(set) => set.hash(state),
+            Value::Inst(inst) => inst.hash(state),
+            Value::Uuid(uuid) => uuid.hash(state),
+            Value::Tagged(tag, value) => (tag, value).hash(state),
+        }
+    }
+}
+
+/// Lays out EDN written on a single line
+pub(crate) fn layout(edn: &str, width: usize) -> String {
+    let tree = cst::parse_with_dialect(edn, Dialect::Edn);
+    tree.forms.iter().map(|node| node.form.to_doc().render(width)).collect()
+}
+
+pub(crate) fn write_string(f: &mut impl fmt::Write, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '\r' => f.write_str("\\r")?,
+            '\u{8}' => f.write_str("\\b")?,
+            '\u{c}' => f.write_str("\\f")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+pub(crate) fn write_char(f: &mut impl fmt::Write, c: char) -> fmt::Result {
+    match c {
+        '\n' => f.write_str("\\newline"),
+        ' ' => f.write_str("\\space"),
+        '\t' => f.write_str("\\tab"),
+        '\r' => f.write_str("\\return"),
+        '\u{8}' => f.write_str("\\backspace"),
+        '\u{c}' => f.write_str("\\formfeed"),
+        c if c.is_control() || c.is_whitespace() => write!(f, "\\u{:04x}", c as u32),
+        c => write!(f, "\\{}", c),
+    }
+}
+
+pub(crate) fn write_float(f: &mut impl fmt::Write, n: f64) -> fmt::Result {
+    if n.is_nan() {
+        f.write_str("##NaN")
+    } else if n.is_infinite() {
+        f.write_str(if n > 0.0 { "##Inf" } else { "##-Inf" })
+    } else {
+        // Debug keeps the `.0` of whole numbers, so the value reads back as a float
+        write!(f, "{:?}", n)
+    }
+}
+
+fn write_seq<'v>(f: &mut fmt::Formatter<'_>, open: &str, values: impl IntoIterator<Item = &'v Value>, close: &str) -> fmt::Result {
+    f.write_str(open)?;
+    for (i, value) in values.into_iter().enumerate() {
+        if i > 0 {
+            f.write_str(" ")?;
+        }
+        write!(f, "{}", value)?;
+    }
+    f.write_str(close)
+}
+
+/// Writes the value as EDN on a single line, the way `pr-str` does
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nil => f.write_str("nil"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::BigInt(n) => write!(f, "{}N", n),
+            Value::Float(n) => write_float(f, *n),
+            Value::BigDecimal(digits) => write!(f, "{}M", digits),
+            Value::Ratio { numerator, denominator } => write!(f, "{}/{}", numerator, denominator),
+            Value::String(s) => write_string(f, s),
+            Value::Char(c) => write_char(f, *c),
+            Value::Keyword(keyword) => write!(f, "{}", keyword),
+            Value::Symbol(symbol) => write!(f, "{}", symbol),
+            Value::List(values) => write_seq(f, "(", values, ")"),
+            Value::Vector(values) => write_seq(f, "[", values, "]"),
+            Value::Map(map) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{} {}", key, value)?;
+                }
+                f.write_str("}")
+            },
+            Value::Set(set) => write_seq(f, "#{", set, "}"),
+            Value::Inst(inst) => write!(f, "#inst \"{}\"", inst),
+            Value::Uuid(uuid) => write!(f, "#uuid \"{}\"", uuid),
+            Value::Tagged(tag, value) => write!(f, "#{} {}", tag, value),
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use std::fs::read_to_string;
-use formatter;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
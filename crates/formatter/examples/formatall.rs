@@ -1,7 +1,5 @@
 use std::{fs::read_to_string, path::Path};
 use walkdir::WalkDir;
-use formatter;
-use rayon::prelude::*;
 
 fn format(file: &Path) {
     let data = read_to_string(file).unwrap();
@@ -0,0 +1,339 @@
+use std::fmt;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::rules::{Indent, Pattern, Rule, Rules};
+
+/// Formatter settings, read from the `settings.json` of a repository.
+///
+/// The default keeps the output of `xformat`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Strips or keeps the spaces at the end of every line. Unset, as `xformat` does,
+    /// they are stripped from code lines and kept in comments
+    pub remove_trailing_whitespace: Option<bool>,
+    /// Adds a line break at the end of the file if there is none
+    pub insert_new_line_at_the_end_of_file: bool,
+    pub remove_empty_lines_at_the_beginning: bool,
+    /// Replaces the whitespace at the end of the file with a single line break
+    pub remove_empty_lines_at_the_end: bool,
+    /// Strips the spaces after an opening and before a closing delimiter on the same line
+    pub remove_spaces_between_parens: bool,
+    /// The built-in rules, extended or overridden by the `rules` and `patterns` of the settings
+    pub rules: Rules,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            remove_trailing_whitespace: None,
+            insert_new_line_at_the_end_of_file: false,
+            remove_empty_lines_at_the_beginning: false,
+            remove_empty_lines_at_the_end: true,
+            remove_spaces_between_parens: false,
+            rules: Rules::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Json(serde_json::Error),
+    /// The value at the dotted key path, e.g. `settings.removeTrailingWhitespace`, has a wrong type
+    InvalidValue { key: String, expected: &'static str },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Json(error) => write!(f, "invalid JSON: {}", error),
+            ConfigError::InvalidValue { key, expected } => write!(f, "`{}` must be {}", key, expected),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn invalid(key: &str, expected: &'static str) -> ConfigError {
+    ConfigError::InvalidValue { key: key.to_owned(), expected }
+}
+
+fn object<'v>(value: &'v Value, key: &str) -> Result<&'v Map<String, Value>, ConfigError> {
+    value.as_object().ok_or_else(|| invalid(key, "an object"))
+}
+
+/// A boolean, or the string `"true"` or `"false"`
+fn boolean(value: &Value, key: &str) -> Result<bool, ConfigError> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        Value::String(s) if s == "true" => Ok(true),
+        Value::String(s) if s == "false" => Ok(false),
+        _ => Err(invalid(key, "true or false")),
+    }
+}
+
+/// A string parsed by `Rule::from_str`, e.g. `"block 1, inner 2 0"`,
+/// or `{"specialArguments": N | [0, ..., N - 1], "otherArguments": "list" | "body"}`
+/// where the special arguments are given by their number or by their indices,
+/// which must be the first ones. That is `block N` with a body, `list N` with a list,
+/// and the function call indentation with a list and no special arguments
+fn rule(value: &Value, key: &str, unknown: &mut Vec<String>) -> Result<Rule, ConfigError> {
+    if let Value::String(s) = value {
+        return s.parse().map_err(|_| invalid(key, "\"fn\" or indents like \"block N, inner DEPTH INDEX\""));
+    }
+
+    let mut special_arguments = 0;
+    let mut list = false;
+    for (name, value) in object(value, key)? {
+        let key = format!("{}.{}", key, name);
+        match name.as_str() {
+            "specialArguments" => {
+                special_arguments = match value {
+                    Value::Array(indices) => {
+                        let first = indices.iter().enumerate().all(|(i, index)| index.as_u64() == Some(i as u64));
+                        if !first {
+                            return Err(invalid(&key, "the indices 0 to N - 1 of the first N arguments"));
+                        }
+                        indices.len()
+                    },
+                    _ => value.as_u64().ok_or_else(|| invalid(&key, "a number or an array of indices"))? as usize,
+                }
+            },
+            "otherArguments" => {
+                list = match value.as_str() {
+                    Some("list") => true,
+                    Some("body") => false,
+                    _ => return Err(invalid(&key, "\"list\" or \"body\"")),
+                }
+            },
+            _ => unknown.push(key),
+        }
+    }
+    Ok(match (list, special_arguments) {
+        (true, 0) => Rule::default(),
+        (true, n) => Rule(vec![Indent::List(n)]),
+        (false, n) => Rule(vec![Indent::Block(n)]),
+    })
+}
+
+/// `{"prefix": "def", "rule": "inner"}` or `{"regex": "-let$", "rule": "block 1"}`
+fn pattern(value: &Value, key: &str, unknown: &mut Vec<String>) -> Result<(Pattern, Rule), ConfigError> {
+    let mut pattern = None;
+    let mut rule = None;
+    for (name, value) in object(value, key)? {
+        let key = format!("{}.{}", key, name);
+        match name.as_str() {
+            "prefix" => {
+                let prefix = value.as_str().ok_or_else(|| invalid(&key, "a string"))?;
+                pattern = Some(Pattern::Prefix(prefix.to_owned()));
+            },
+            "regex" => {
+                let regex = value.as_str().and_then(|regex| Regex::new(regex).ok());
+                pattern = Some(Pattern::Regex(regex.ok_or_else(|| invalid(&key, "a valid regular expression"))?));
+            },
+            "rule" => rule = Some(self::rule(value, &key, unknown)?),
+            _ => unknown.push(key),
+        }
+    }
+    match (pattern, rule) {
+        (Some(pattern), Some(rule)) => Ok((pattern, rule)),
+        _ => Err(invalid(key, "an object with a \"prefix\" or \"regex\" and a \"rule\"")),
+    }
+}
+
+impl Config {
+    /// Reads the settings, returning the config and the dotted paths of the keys
+    /// it doesn't know, which are ignored
+    pub fn from_json(json: &str) -> Result<(Config, Vec<String>), ConfigError> {
+        let root: Value = serde_json::from_str(json).map_err(ConfigError::Json)?;
+        let mut config = Config::default();
+        let mut unknown = Vec::new();
+
+        for (name, value) in object(&root, "settings.json")? {
+            match name.as_str() {
+                "settings" => {
+                    for (name, value) in object(value, name)? {
+                        let key = format!("settings.{}", name);
+                        let setting = match name.as_str() {
+                            "removeTrailingWhitespace" => {
+                                config.remove_trailing_whitespace = Some(boolean(value, &key)?);
+                                continue;
+                            },
+                            "insertNewLineAtTheEndOfFile" => &mut config.insert_new_line_at_the_end_of_file,
+                            "removeEmptyLinesAtTheBeginning" => &mut config.remove_empty_lines_at_the_beginning,
+                            "removeEmptyLinesAtTheEnd" => &mut config.remove_empty_lines_at_the_end,
+                            "removeSpacesBetweenParens" => &mut config.remove_spaces_between_parens,
+                            _ => {
+                                unknown.push(key);
+                                continue;
+                            },
+                        };
+                        *setting = boolean(value, &key)?;
+                    }
+                },
+                "rules" => {
+                    for (symbol, value) in object(value, name)? {
+                        let rule = rule(value, &format!("rules.{}", symbol), &mut unknown)?;
+                        config.rules.insert(symbol.clone(), rule);
+                    }
+                },
+                // Listed in the order they are tried
+                "patterns" => {
+                    let patterns = value.as_array().ok_or_else(|| invalid(name, "an array"))?;
+                    let patterns = patterns
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| pattern(value, &format!("patterns[{}]", i), &mut unknown))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    for (pattern, rule) in patterns.into_iter().rev() {
+                        config.rules.insert_pattern(pattern, rule);
+                    }
+                },
+                _ => unknown.push(name.clone()),
+            }
+        }
+        Ok((config, unknown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokenizer::dialect::Dialect;
+
+    use super::{Config, ConfigError};
+    use crate::format_with_config;
+    use crate::rules::{Indent, Rule, Rules};
+
+    fn config(json: &str) -> Config {
+        let (config, unknown) = Config::from_json(json).unwrap();
+        assert_eq!(unknown, Vec::<String>::new());
+        config
+    }
+
+    fn format(json: &str, source: &str) -> String {
+        format_with_config(source, Dialect::Clojure, &config(json))
+    }
+
+    fn invalid(json: &str) -> (String, &'static str) {
+        match Config::from_json(json) {
+            Err(ConfigError::InvalidValue { key, expected }) => (key, expected),
+            other => panic!("expected an invalid value, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(config("{}"), Config::default());
+        assert_eq!(format("{}", "(a)"), "(a)");
+        assert_eq!(format("{}", "(a)  \n\n"), "(a)\n");
+        assert_eq!(format("{}", "(a   \n b) ; c  \n"), "(a\n b) ; c  \n");
+        assert_eq!(format("{}", "\n\n( a )"), "\n\n( a )");
+    }
+
+    #[test]
+    fn remove_trailing_whitespace() {
+        let source = "(a   \n b) ; c  \n(d)";
+        assert_eq!(format(r#"{"settings": {"removeTrailingWhitespace": true}}"#, source), "(a\n b) ; c\n(d)");
+        assert_eq!(format(r#"{"settings": {"removeTrailingWhitespace": false}}"#, source), "(a   \n b) ; c  \n(d)");
+        assert_eq!(format(r#"{"settings": {"removeTrailingWhitespace": "false"}}"#, "(a)  \n  \n(b)"), "(a)  \n  \n(b)");
+    }
+
+    #[test]
+    fn insert_new_line_at_the_end_of_file() {
+        let json = r#"{"settings": {"insertNewLineAtTheEndOfFile": true}}"#;
+        assert_eq!(format(json, "(a)"), "(a)\n");
+        assert_eq!(format(json, "(a)\n"), "(a)\n");
+        assert_eq!(format(json, ""), "");
+        assert_eq!(format(r#"{"settings": {"insertNewLineAtTheEndOfFile": "false"}}"#, "(a)"), "(a)");
+    }
+
+    #[test]
+    fn remove_empty_lines() {
+        let source = "\n\n(a)\n\n\n";
+        assert_eq!(format(r#"{"settings": {"removeEmptyLinesAtTheBeginning": true}}"#, source), "(a)\n");
+        assert_eq!(format(r#"{"settings": {"removeEmptyLinesAtTheBeginning": true}}"#, "\u{feff}\n(a)"), "\u{feff}(a)");
+        assert_eq!(format(r#"{"settings": {"removeEmptyLinesAtTheEnd": false}}"#, source), "\n\n(a)\n\n\n");
+        assert_eq!(format(r#"{"settings": {"removeEmptyLinesAtTheEnd": true}}"#, source), "\n\n(a)\n");
+    }
+
+    #[test]
+    fn remove_spaces_between_parens() {
+        let json = r#"{"settings": {"removeSpacesBetweenParens": true}}"#;
+        assert_eq!(format(json, "( a [ b ] )"), "(a [b])");
+        assert_eq!(format(json, "(a ; c\n )"), "(a ; c\n )");
+        assert_eq!(format(r#"{"settings": {"removeSpacesBetweenParens": false}}"#, "( a )"), "( a )");
+    }
+
+    #[test]
+    fn rules() {
+        let config = config(r#"{
+            "rules": {
+                "my-let": "block 1",
+                "defn": "fn",
+                "my-fn": "inner",
+                "my-letfn": "block 1, inner 2 0",
+                "when": {"specialArguments": 2, "otherArguments": "body"},
+                "if": {"specialArguments": [0], "otherArguments": "body"},
+                "let": {"specialArguments": [], "otherArguments": "list"},
+                "cond->": {"specialArguments": [0, 1], "otherArguments": "list"}
+            }
+        }"#);
+        assert_eq!(config.rules.get("my-let"), Some(&Rule(vec![Indent::Block(1)])));
+        assert_eq!(config.rules.get("defn"), Some(&Rule(vec![])));
+        assert_eq!(config.rules.get("my-letfn"), Some(&Rule(vec![Indent::Block(1), Indent::Inner(2, Some(0))])));
+        assert_eq!(config.rules.get("when"), Some(&Rule(vec![Indent::Block(2)])));
+        assert_eq!(config.rules.get("if"), Some(&Rule(vec![Indent::Block(1)])));
+        assert_eq!(config.rules.get("my-fn"), Some(&Rule(vec![Indent::Inner(0, None)])));
+        assert_eq!(config.rules.get("let"), Some(&Rule(vec![])));
+        assert_eq!(config.rules.get("cond->"), Some(&Rule(vec![Indent::List(2)])));
+        assert_eq!(config.rules.get("do"), Rules::default().get("do"));
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let (config, unknown) = Config::from_json(r#"{
+            "settings": {"removeTrailingWhitespace": true, "tabs": 4},
+            "rules": {"let": {"specialArguments": 1, "comment": "x"}},
+            "patterns": [{"prefix": "def", "rule": "inner", "note": "x"}],
+            "version": 1
+        }"#).unwrap();
+        assert_eq!(unknown, ["patterns[0].note", "rules.let.comment", "settings.tabs", "version"]);
+        assert_eq!(config.remove_trailing_whitespace, Some(true));
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert!(matches!(Config::from_json("{"), Err(ConfigError::Json(_))));
+        assert_eq!(invalid("[]"), ("settings.json".to_owned(), "an object"));
+        assert_eq!(invalid(r#"{"settings": []}"#), ("settings".to_owned(), "an object"));
+        assert_eq!(invalid(r#"{"settings": {"removeEmptyLinesAtTheEnd": 1}}"#), ("settings.removeEmptyLinesAtTheEnd".to_owned(), "true or false"));
+        assert_eq!(invalid(r#"{"settings": {"removeTrailingWhitespace": "yes"}}"#).0, "settings.removeTrailingWhitespace");
+        assert_eq!(invalid(r#"{"rules": {"let": "block"}}"#).0, "rules.let");
+        assert_eq!(invalid(r#"{"rules": {"let": 1}}"#), ("rules.let".to_owned(), "an object"));
+        assert_eq!(invalid(r#"{"rules": {"let": {"specialArguments": "1"}}}"#).0, "rules.let.specialArguments");
+        assert_eq!(invalid(r#"{"rules": {"let": {"specialArguments": [1]}}}"#).0, "rules.let.specialArguments");
+        assert_eq!(invalid(r#"{"rules": {"let": {"specialArguments": ["bindings"]}}}"#).0, "rules.let.specialArguments");
+        assert_eq!(invalid(r#"{"rules": {"let": {"otherArguments": "rest"}}}"#).0, "rules.let.otherArguments");
+        assert_eq!(invalid(r#"{"patterns": {}}"#), ("patterns".to_owned(), "an array"));
+        assert_eq!(invalid(r#"{"patterns": [{"regex": "(", "rule": "inner"}]}"#).0, "patterns[0].regex");
+        assert_eq!(invalid(r#"{"patterns": [{"prefix": "def"}]}"#).0, "patterns[0]");
+    }
+
+    #[test]
+    fn shipped_settings() {
+        let config = config(include_str!("../../../settings.json"));
+        assert_eq!(config.remove_trailing_whitespace, Some(true));
+        assert!(config.insert_new_line_at_the_end_of_file);
+        assert!(config.remove_empty_lines_at_the_beginning);
+        assert!(config.remove_empty_lines_at_the_end);
+        assert!(config.remove_spaces_between_parens);
+        let mut rules = Rules::default();
+        rules.insert("fn", Rule::default());
+        assert_eq!(config.rules, rules);
+
+        let format = |source| format_with_config(source, Dialect::Clojure, &config);
+        assert_eq!(format("(fn [x]\nx)"), "(fn [x]\n    x)\n");
+        assert_eq!(format("\n(let [ a 1 ]  \na) ; c  \n(b)"), "(let [a 1]\n  a) ; c\n(b)\n");
+    }
+}
@@ -2,8 +2,17 @@
 /// Done in one day :(
 /// To be rewritten
 use tokenizer::Tokenizer;
+use tokenizer::dialect::Dialect;
 use tokenizer::token::{Token, TokenType};
 
+mod config;
+mod namespace;
+mod rules;
+
+pub use config::{Config, ConfigError};
+pub use namespace::Namespace;
+pub use rules::{Indent, Pattern, Rule, Rules};
+
 #[derive(Debug, Clone, Copy)]
 struct Alignment {
     indent: usize,
@@ -55,8 +64,26 @@ impl Alignment {
     }
 }
 
+/// A form with a rule for the forms nested in it
+struct Scope<'a> {
+    rule: &'a Rule,
+    /// Depth of the elements of the form
+    depth: usize,
+    /// Elements of the form seen so far, the head included
+    elements: usize,
+    /// Forms which belong to the last element after its prefixes, e.g. 2 after `^`
+    pending: usize,
+}
+
 struct LookaheadCursor<'a> {
     tokenizer: Tokenizer<'a>,
+    config: &'a Config,
+    namespace: Namespace,
+    /// Nesting depth of the current token
+    depth: usize,
+    /// Enclosing forms with nested rules, the innermost last
+    scopes: Vec<Scope<'a>>,
+    prev: Option<Token<'a>>,
     cur: Option<Token<'a>>,
     ahead1: Option<Token<'a>>,
     ahead2: Option<Token<'a>>
@@ -64,6 +91,7 @@ struct LookaheadCursor<'a> {
 
 impl<'a> LookaheadCursor<'a> {
     fn next(&mut self) -> Option<Token<'a>> {
+        self.prev = self.cur;
         self.cur = self.ahead1;
         self.ahead1 = self.ahead2;
         self.ahead2 = self.tokenizer.next();
@@ -78,14 +106,15 @@ impl<'a> LookaheadCursor<'a> {
         self.ahead1
     }
     
-    fn lookahead2(&mut self) -> Option<Token<'a>> {
-        self.ahead2
-    }
-    
-    fn new(stream: &'a str) -> Self {
-        let mut tokenizer = Tokenizer::from(stream);
+    fn new(stream: &'a str, dialect: Dialect, config: &'a Config) -> Self {
+        let tokenizer = Tokenizer::from(stream).with_dialect(dialect);
         let mut this = LookaheadCursor {
-            tokenizer: tokenizer,
+            tokenizer,
+            config,
+            namespace: Namespace::from_source(stream, dialect),
+            depth: 0,
+            scopes: Vec::new(),
+            prev: None,
             cur: None,
             ahead1: None,
             ahead2: None
@@ -96,18 +125,84 @@ impl<'a> LookaheadCursor<'a> {
         
         this
     }
+
+    /// Counts the elements of the innermost scope if the token is one of them
+    fn count_element(&mut self, tok: Token) {
+        let Some(scope) = self.scopes.last_mut().filter(|scope| scope.depth == self.depth) else {
+            return;
+        };
+        if tok.is_trivia() || tok.is_right() {
+            return;
+        }
+
+        if scope.pending > 0 {
+            scope.pending -= 1;
+        } else {
+            scope.elements += 1;
+        }
+        scope.pending += match tok.kind() {
+            TokenType::Metadata => 2,
+            TokenType::Quote
+            | TokenType::SynQuote
+            | TokenType::Unquote
+            | TokenType::UnquoteSplicing
+            | TokenType::Deref
+            | TokenType::VarQuote
+            | TokenType::Discard
+            | TokenType::ReaderConditional
+            | TokenType::ReaderConditionalSplicing
+            | TokenType::NamespacedMap
+            | TokenType::Tag
+            | TokenType::Dispatch => 1,
+            _ => 0,
+        };
+    }
+
+    /// True if an enclosing form has an inner rule for the list whose elements are at the current depth
+    fn is_inner(&self) -> bool {
+        self.scopes.iter().any(|scope| {
+            scope.rule.0.iter().any(|indent| match *indent {
+                // The argument index excludes the head
+                Indent::Inner(depth, index) => {
+                    depth > 0 && self.depth - scope.depth == depth && index.is_none_or(|i| scope.elements == i + 2)
+                },
+                Indent::Block(_) | Indent::List(_) => false,
+            })
+        })
+    }
 }
 
 fn format_whitespace(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment) -> Alignment {
+    let config = cursor.config;
+    let s = cursor.current().unwrap().value();
+
+    let trailing = config.remove_trailing_whitespace;
+
     // Strip whitespace at the end of the file
     let Some(la1) = cursor.lookahead1() else {
-        buf.push('\n');
+        if config.remove_empty_lines_at_the_end {
+            buf.push('\n');
+        } else if trailing == Some(false) {
+            buf.push_str(s);
+        } else {
+            buf.extend(s.chars().filter(|&c| c == '\n'));
+        }
         return alignment;
     };
-    
-    let s = cursor.current().unwrap().value();
+
+    // Nothing but the BOM before
+    if config.remove_empty_lines_at_the_beginning && cursor.prev.is_none_or(|prev| prev.kind() == TokenType::Bom) {
+        return alignment;
+    }
 
     let Some(last_newline) = s.rfind('\n') else {
+        // The indentation after a comment is kept
+        let after_open = cursor.prev.is_some_and(|prev| prev.is_left());
+        let before_close = la1.is_right() && cursor.prev.is_none_or(|prev| prev.kind() != TokenType::Comment);
+        if config.remove_spaces_between_parens && (after_open || before_close) {
+            return alignment;
+        }
+
         // We don't have new lines 
         // and the next token is non-whitespace (by construction of tokenizer)
         // Just push whitespaces
@@ -117,22 +212,20 @@ fn format_whitespace(buf: &mut String, cursor: &mut LookaheadCursor, alignment:
     
     
     // + 1 to put newline in left part
-    let (newlines, last_line) = s.split_at(last_newline + 1);
-    let nl_count = newlines.chars().filter(|&c| c == '\n').count();
-    
-    for _ in 0..nl_count {
-        buf.push('\n')
+    let (newlines, _) = s.split_at(last_newline + 1);
+    if trailing == Some(false) {
+        buf.push_str(newlines);
+    } else {
+        buf.extend(newlines.chars().filter(|&c| c == '\n'));
     }
     
     for _ in 0..alignment.indent {
         buf.push(' ')
     }
     
-    if la1.kind() == TokenType::Comment {
-        if !la1.value().starts_with(";;") {
-            for _ in alignment.pos..40 {
-                buf.push(' ');
-            }
+    if la1.kind() == TokenType::Comment && !la1.value().starts_with(";;") {
+        for _ in alignment.pos..40 {
+            buf.push(' ');
         }
     }
     
@@ -142,9 +235,14 @@ fn format_whitespace(buf: &mut String, cursor: &mut LookaheadCursor, alignment:
 fn format_comment(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment) -> Alignment {
     let s = cursor.current().unwrap().value();
     
-    buf.push_str(s);
-    if !s.ends_with('\n') {
+    if cursor.config.remove_trailing_whitespace == Some(true) {
+        buf.push_str(s.trim_end());
         buf.push('\n');
+    } else {
+        buf.push_str(s);
+        if !s.ends_with('\n') {
+            buf.push('\n');
+        }
     }
     
     let Some(la1) = cursor.lookahead1() else {
@@ -190,12 +288,12 @@ fn format_vector(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alig
 
 fn format_map(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment) -> Alignment {
     let tok = cursor.current().expect("Current token must be `[`");
-    assert_eq!(tok.kind(), TokenType::LBrace);
+    assert!(matches!(tok.kind(), TokenType::LBrace | TokenType::LSet));
     
 
     buf.push_str(tok.value());    
     
-    let mut next_alignment = alignment.increase(1);
+    let mut next_alignment = alignment.increase(tok.value().len());
     
     while let Some(tok) = cursor.next() {
         next_alignment = match tok.kind() {
@@ -219,14 +317,20 @@ fn format_word(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignm
     alignment.shift(cursor.current().unwrap().value().len())
 }
 
+/// The BOM is kept as is and takes no space
+fn format_bom(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment) -> Alignment {
+    buf.push_str(cursor.current().unwrap().value());
+    alignment
+}
+
 fn format_fn(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment) -> Alignment {
     let tok = cursor.current().expect("Current token must be `[`");
-    assert_eq!(tok.kind(), TokenType::LParen);
+    assert!(matches!(tok.kind(), TokenType::LParen | TokenType::LAnonFn));
     
 
     // '('
     buf.push_str(tok.value());    
-    let mut inner_alignment = alignment.increase(1);
+    let inner_alignment = alignment.increase(tok.value().len());
     cursor.next();
     
     // macro-name
@@ -273,12 +377,12 @@ fn format_fn(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignmen
 
 fn format_list(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment) -> Alignment {
     let tok = cursor.current().expect("Current token must be `[`");
-    assert_eq!(tok.kind(), TokenType::LParen);
+    assert!(matches!(tok.kind(), TokenType::LParen | TokenType::LAnonFn));
     
 
     buf.push_str(tok.value());    
     
-    let mut next_alignment = alignment.increase(1);
+    let mut next_alignment = alignment.increase(tok.value().len());
     
     while let Some(tok) = cursor.next() {
         next_alignment = match tok.kind() {
@@ -327,7 +431,7 @@ fn format_arg(buf: &mut String, cursor: &mut LookaheadCursor, mut alignment: Ali
             // Format whitespace if any
             alignment = format_ws_lax(buf, cursor, alignment);
             // Format argument
-            return format_arg(buf, cursor, alignment)
+            format_arg(buf, cursor, alignment)
         },
         TokenType::Metadata => {
             // Consume dipatch
@@ -341,19 +445,26 @@ fn format_arg(buf: &mut String, cursor: &mut LookaheadCursor, mut alignment: Ali
             // Format whitespace if any
             alignment = format_ws_lax(buf, cursor, alignment);
             // Format argument
-            return format_arg(buf, cursor, alignment)
+            format_arg(buf, cursor, alignment)
         },
-        TokenType::Quote | TokenType::SynQuote => {
+        TokenType::Quote
+        | TokenType::SynQuote
+        | TokenType::VarQuote
+        | TokenType::Discard
+        | TokenType::ReaderConditional
+        | TokenType::ReaderConditionalSplicing
+        | TokenType::NamespacedMap
+        | TokenType::Tag => {
             // Consume dipatch
             alignment = format(buf, cursor, alignment);
             cursor.next();
             // Format whitespace if any
             alignment = format_ws_lax(buf, cursor, alignment);
             // Format argument
-            return format_arg(buf, cursor, alignment)
+            format_arg(buf, cursor, alignment)
         },
         _ => {
-            return format(buf, cursor, alignment);
+            format(buf, cursor, alignment)
         }
     }
 }
@@ -399,18 +510,20 @@ fn format_one_sparg(buf: &mut String, cursor: &mut LookaheadCursor, mut alignmen
 }
 
 
-fn format_sparg(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment, count: usize) -> Alignment {
+/// Formats `count` special arguments followed by the body, or by the other arguments aligned
+/// with the special ones if `list`
+fn format_sparg(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment, count: usize, list: bool) -> Alignment {
     let tok = cursor.current().expect("Current token must be `[`");
-    assert_eq!(tok.kind(), TokenType::LParen);
+    assert!(matches!(tok.kind(), TokenType::LParen | TokenType::LAnonFn));
     
 
     // '('
     buf.push_str(tok.value());    
-    let mut inner_alignment = alignment.increase(1);
+    let inner_alignment = alignment.increase(tok.value().len());
     cursor.next();
     
     // macro-name
-    let mut macro_alignment = format(buf, cursor, inner_alignment);
+    let macro_alignment = format(buf, cursor, inner_alignment);
     cursor.next();
     
     let mut sparg_alignment = macro_alignment.indent(3);
@@ -423,9 +536,11 @@ fn format_sparg(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Align
         sparg_alignment = format_one_sparg(buf, cursor, sparg_alignment);
     }
     
-    let mut body_alignment = sparg_alignment
-        .set_indent(macro_alignment.indent)
-        .indent(1);
+    let mut body_alignment = if list {
+        sparg_alignment.set_indent(sparg_alignment.align)
+    } else {
+        sparg_alignment.set_indent(macro_alignment.indent).indent(1)
+    };
 
     
     body_alignment = format_ws_lax(buf, cursor, body_alignment);
@@ -468,12 +583,12 @@ fn format_sparg(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Align
 
 fn format_defn(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment) -> Alignment {
     let tok = cursor.current().expect("Current token must be `[`");
-    assert_eq!(tok.kind(), TokenType::LParen);
+    assert!(matches!(tok.kind(), TokenType::LParen | TokenType::LAnonFn));
     
 
     buf.push_str(tok.value());    
     
-    let mut next_alignment = alignment.indent(2).shift(1);
+    let mut next_alignment = alignment.indent(2).shift(tok.value().len());
     
     while let Some(tok) = cursor.next() {
         next_alignment = match tok.kind() {
@@ -494,108 +609,67 @@ fn format_defn(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignm
 
 fn format_sexp(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment) -> Alignment {
     let tok = cursor.current().expect("Current token must be `[`");
-    assert_eq!(tok.kind(), TokenType::LParen);
+    assert!(matches!(tok.kind(), TokenType::LParen | TokenType::LAnonFn));
     
 
+    // Not closed at the end of the source
     let Some(la1) = cursor.lookahead1() else {
-        panic!("'(' Not closed");
+        return format_list(buf, cursor, alignment);
+    };
+
+    let config = cursor.config;
+    let rule = match la1.kind() {
+        TokenType::Symbol => config.rules.resolve(la1.value(), &cursor.namespace),
+        _ => None,
     };
+    let inner = cursor.is_inner();
+    if let Some(rule) = rule.filter(|rule| rule.is_nested()) {
+        cursor.scopes.push(Scope { rule, depth: cursor.depth, elements: 0, pending: 0 });
+    }
+    if inner {
+        return format_defn(buf, cursor, alignment);
+    }
 
     match la1.kind() {
-        TokenType::Keyword => return format_fn(buf, cursor, alignment),
+        TokenType::Keyword | TokenType::Number => return format_fn(buf, cursor, alignment),
         TokenType::Symbol => (),
         _ => return format_list(buf, cursor, alignment)
     }
     
-    let dispatch = la1.value();
-    
-    match dispatch {
-        // zero sparg
-        "alt!!" => format_sparg(buf, cursor, alignment, 0),
-        "alt!" => format_sparg(buf, cursor, alignment, 0),
-        "comment" => format_sparg(buf, cursor, alignment, 0),
-        "cond" => format_sparg(buf, cursor, alignment, 0),
-        "delay" => format_sparg(buf, cursor, alignment, 0),
-        "do" => format_sparg(buf, cursor, alignment, 0),
-        "finally" => format_sparg(buf, cursor, alignment, 0),
-        "future" => format_sparg(buf, cursor, alignment, 0),
-        "go" => format_sparg(buf, cursor, alignment, 0),
-        "thread" => format_sparg(buf, cursor, alignment, 0),
-        "try" => format_sparg(buf, cursor, alignment, 0),
-
-        // one sparg
-        "ns" => format_sparg(buf, cursor, alignment, 1),
-        "if" => format_sparg(buf, cursor, alignment, 1),
-        "if-not" => format_sparg(buf, cursor, alignment, 1),
-        "case" => format_sparg(buf, cursor, alignment, 1),
-        "when" => format_sparg(buf, cursor, alignment, 1),
-        "while" => format_sparg(buf, cursor, alignment, 1),
-        "cond->" => format_sparg(buf, cursor, alignment, 1),
-        "cond->>" => format_sparg(buf, cursor, alignment, 1),
-        "when-not" => format_sparg(buf, cursor, alignment, 1),
-        "when-first" => format_sparg(buf, cursor, alignment, 1),
-        "doto" => format_sparg(buf, cursor, alignment, 1),
-        "locking" => format_sparg(buf, cursor, alignment, 1),
-        "fdef" => format_sparg(buf, cursor, alignment, 1),
-        "extend" => format_sparg(buf, cursor, alignment, 1),
-        "let" => format_sparg(buf, cursor, alignment, 1),
-        "binding" => format_sparg(buf, cursor, alignment, 1),
-        "loop" => format_sparg(buf, cursor, alignment, 1),
-        "for" => format_sparg(buf, cursor, alignment, 1),
-        "doseq" => format_sparg(buf, cursor, alignment, 1),
-        "dotimes" => format_sparg(buf, cursor, alignment, 1),
-        "when-let" => format_sparg(buf, cursor, alignment, 1),
-        "if-let" => format_sparg(buf, cursor, alignment, 1),
-        "when-some" => format_sparg(buf, cursor, alignment, 1),
-        "if-some" => format_sparg(buf, cursor, alignment, 1),
-        "this-as" => format_sparg(buf, cursor, alignment, 1),
-        "testing" => format_sparg(buf, cursor, alignment, 1),
-        "async" => format_sparg(buf, cursor, alignment, 1),
-        "go-loop" => format_sparg(buf, cursor, alignment, 1),
-
-        // Two sparg
-        "condp" => format_sparg(buf, cursor, alignment, 2),
-        "as->" => format_sparg(buf, cursor, alignment, 2),
-        "catch" => format_sparg(buf, cursor, alignment, 2),
-        "are" => format_sparg(buf, cursor, alignment, 2),
-
-        // defn format
-        "fn" => format_defn(buf, cursor, alignment),
-        "def" => format_defn(buf, cursor, alignment),
-        "defn" => format_defn(buf, cursor, alignment),
-        "bound-fn" => format_defn(buf, cursor, alignment),
-        "defmethod" => format_defn(buf, cursor, alignment),
-        "run" => format_defn(buf, cursor, alignment),
-        "run*" => format_defn(buf, cursor, alignment),
-        "fresh" => format_defn(buf, cursor, alignment),
-        "deftest" => format_defn(buf, cursor, alignment),
-        "use-fixtures" => format_defn(buf, cursor, alignment),
-
-        // Misc
-        // (proxy '(2 nil nil (:defn)))
-        // (reify '(:defn (1)))
-        // (deftype '(2 nil nil (:defn)))
-        // (defrecord '(2 nil nil (:defn)))
-        // (defprotocol '(1 (:defn)))
-        // (definterface '(1 (:defn)))
-        // (extend-protocol '(1 :defn))
-        // (extend-type '(1 :defn))
-        // (specify '(1 :defn))
-        // (specify! '(1 :defn))
-        // (letfn '(1 ((:defn)) nil))
-        
-        _ => format_fn(buf, cursor, alignment)
+    match rule.and_then(Rule::own) {
+        Some(Indent::Block(n)) => format_sparg(buf, cursor, alignment, n, false),
+        Some(Indent::List(n)) => format_sparg(buf, cursor, alignment, n, true),
+        Some(Indent::Inner(..)) => format_defn(buf, cursor, alignment),
+        None => format_fn(buf, cursor, alignment),
     }
 }
 
+/// Formats a collection, its elements are one level deeper
+fn format_nested(
+    buf: &mut String,
+    cursor: &mut LookaheadCursor,
+    alignment: Alignment,
+    format_collection: fn(&mut String, &mut LookaheadCursor, Alignment) -> Alignment,
+) -> Alignment {
+    cursor.depth += 1;
+    let alignment = format_collection(buf, cursor, alignment);
+    cursor.depth -= 1;
+    // Leaving a form with a nested rule
+    while cursor.scopes.last().is_some_and(|scope| scope.depth > cursor.depth) {
+        cursor.scopes.pop();
+    }
+    alignment
+}
+
 fn format(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment) -> Alignment {
     let Some(tok) = cursor.current() else {
         return alignment;
     };
+    cursor.count_element(tok);
     
     match tok.kind() {
         TokenType::LBracket => {
-            format_vector(buf, cursor, alignment)
+            format_nested(buf, cursor, alignment, format_vector)
         },
         TokenType::Whitespace => {
             format_whitespace(buf, cursor, alignment)
@@ -604,13 +678,21 @@ fn format(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment)
         TokenType::Comment => format_comment(buf, cursor, alignment),
         TokenType::Deref => format_word(buf, cursor, alignment),
         TokenType::Dispatch => format_word(buf, cursor, alignment),
-        TokenType::Error => format_word(buf, cursor, alignment),
+        TokenType::Regex => format_word(buf, cursor, alignment),
+        TokenType::VarQuote => format_word(buf, cursor, alignment),
+        TokenType::Discard => format_word(buf, cursor, alignment),
+        TokenType::ReaderConditional => format_word(buf, cursor, alignment),
+        TokenType::ReaderConditionalSplicing => format_word(buf, cursor, alignment),
+        TokenType::SymbolicValue => format_word(buf, cursor, alignment),
+        TokenType::NamespacedMap => format_word(buf, cursor, alignment),
+        TokenType::Tag => format_word(buf, cursor, alignment),
+        TokenType::Error(_) => format_word(buf, cursor, alignment),
         TokenType::Keyword => format_word(buf, cursor, alignment),
-        TokenType::LBrace => {
-            format_map(buf, cursor, alignment)
+        TokenType::LBrace | TokenType::LSet => {
+            format_nested(buf, cursor, alignment, format_map)
         },
-        TokenType::LParen => {
-            format_sexp(buf, cursor, alignment)
+        TokenType::LParen | TokenType::LAnonFn => {
+            format_nested(buf, cursor, alignment, format_sexp)
         },
         TokenType::Metadata => format_word(buf, cursor, alignment),
         TokenType::Quote => format_word(buf, cursor, alignment),
@@ -619,18 +701,183 @@ fn format(buf: &mut String, cursor: &mut LookaheadCursor, alignment: Alignment)
         TokenType::RParen => format_word(buf, cursor, alignment),
         TokenType::String => format_word(buf, cursor, alignment),
         TokenType::Symbol => format_word(buf, cursor, alignment),
+        TokenType::Number => format_word(buf, cursor, alignment),
         TokenType::SynQuote => format_word(buf, cursor, alignment),
         TokenType::Unquote => format_word(buf, cursor, alignment),
         TokenType::UnquoteSplicing => format_word(buf, cursor, alignment),
+        TokenType::Shebang => format_word(buf, cursor, alignment),
+        TokenType::Bom => format_bom(buf, cursor, alignment),
     }
 }
 
 pub fn xformat(s: &str) -> String {
-    let mut cur = LookaheadCursor::new(s);
+    xformat_with_dialect(s, Dialect::default())
+}
+
+/// Formats the source read in the given dialect
+pub fn xformat_with_dialect(s: &str, dialect: Dialect) -> String {
+    format_with_config(s, dialect, &Config::default())
+}
+
+/// Formats the source read in the given dialect with the settings
+pub fn format_with_config(s: &str, dialect: Dialect, config: &Config) -> String {
+    let mut cur = LookaheadCursor::new(s, dialect, config);
     let mut buf = String::new();
     let mut align = Alignment::new();
-    while let Some(tok) = cur.next() {
+    while cur.next().is_some() {
         align = format(&mut buf, &mut cur, align);
     }
+    if config.insert_new_line_at_the_end_of_file && !buf.is_empty() && !buf.ends_with('\n') {
+        buf.push('\n');
+    }
     buf
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use tokenizer::dialect::Dialect;
+
+    use super::{format_with_config, xformat, Config, Indent, Pattern, Rule, Rules};
+
+    fn with_rules(source: &str, rules: Rules) -> String {
+        format_with_config(source, Dialect::Clojure, &Config { rules, ..Config::default() })
+    }
+
+    #[test]
+    fn dialects() {
+        let source = "#!/usr/bin/env bb\n(defn f [x]\nx)\n#js {:a 1}\n";
+        let expected = "#!/usr/bin/env bb\n(defn f [x]\n  x)\n#js {:a 1}\n";
+        assert_eq!(xformat(source), expected);
+        for dialect in [Dialect::Clojure, Dialect::ClojureScript, Dialect::Babashka] {
+            assert_eq!(format_with_config(source, dialect, &Config::default()), expected);
+        }
+    }
+
+    #[test]
+    fn block_forms() {
+        assert_eq!(xformat("(let [a 1\nb 2]\n(+ a b))"), "(let [a 1\n      b 2]\n  (+ a b))");
+        assert_eq!(xformat("(if a\nb\nc)"), "(if a\n  b\n  c)");
+        assert_eq!(xformat("(do\na\nb)"), "(do\n  a\n  b)");
+        assert_eq!(xformat("(do a\nb)"), "(do a\n b)");
+        assert_eq!(xformat("(when-let [x (f)]\n(g x))"), "(when-let [x (f)]\n  (g x))");
+        assert_eq!(xformat("(condp = x\n1 a\n2 b)"), "(condp = x\n  1 a\n  2 b)");
+        assert_eq!(xformat("(catch Exception e\n(h e))"), "(catch Exception e\n  (h e))");
+    }
+
+    #[test]
+    fn inner_forms() {
+        assert_eq!(xformat("(defn f [x]\nx)"), "(defn f [x]\n  x)");
+        assert_eq!(xformat("(fn [x]\nx)"), "(fn [x]\n  x)");
+        assert_eq!(xformat("(deftest t\n(is a))"), "(deftest t\n  (is a))");
+    }
+
+    #[test]
+    fn function_calls() {
+        assert_eq!(xformat("(foo a\nb)"), "(foo a\n     b)");
+        assert_eq!(xformat("(foo\na\nb)"), "(foo\n a\n b)");
+        assert_eq!(xformat("(-> x\n(f)\n(g))"), "(-> x\n    (f)\n    (g))");
+        assert_eq!(xformat("(:k m\nd)"), "(:k m\n    d)");
+        assert_eq!(xformat("([a]\nb)"), "([a]\n b)");
+        assert_eq!(xformat("#(foo %\nbar)"), "#(foo %\n      bar)");
+        assert_eq!(xformat("(a b\n(c d"), "(a b\n   (c d");
+    }
+
+    #[test]
+    fn nested_rules() {
+        assert_eq!(
+            xformat("(reify P\n(m [this]\nx)\nQ\n(n [_ a]\n(f a\nb)))"),
+            "(reify P\n  (m [this]\n    x)\n  Q\n  (n [_ a]\n    (f a\n       b)))",
+        );
+        assert_eq!(xformat("(proxy [Object] []\n(toString []\n\"x\"))"), "(proxy [Object] []\n  (toString []\n    \"x\"))");
+        assert_eq!(xformat("(deftype T [a b]\nP\n(m [this]\na))"), "(deftype T [a b]\n  P\n  (m [this]\n    a))");
+        assert_eq!(xformat("(defrecord R [a]\nP\n(m [this]\na))"), "(defrecord R [a]\n  P\n  (m [this]\n    a))");
+        assert_eq!(xformat("(defprotocol P\n\"doc\"\n(m [this]\n\"doc\"))"), "(defprotocol P\n  \"doc\"\n  (m [this]\n    \"doc\"))");
+        assert_eq!(xformat("(extend-protocol P\nString\n(m [this]\nthis))"), "(extend-protocol P\n  String\n  (m [this]\n    this))");
+        assert_eq!(xformat("(extend-type String\nP\n(m [this]\nthis))"), "(extend-type String\n  P\n  (m [this]\n    this))");
+    }
+
+    #[test]
+    fn letfn() {
+        assert_eq!(
+            xformat("(letfn [(f [x]\n(g x))\n(g [y]\ny)]\n(f 1))"),
+            "(letfn [(f [x]\n          (g x))\n        (g [y]\n          y)]\n  (f 1))",
+        );
+        // Only the bindings have the inner rule
+        assert_eq!(xformat("(letfn [(f [x] x)]\n(foo a\nb))"), "(letfn [(f [x] x)]\n  (foo a\n       b))");
+    }
+
+    #[test]
+    fn nested_two_levels() {
+        assert_eq!(
+            xformat("(defn make [a]\n(let [x (reify P\n(m [this]\n(let [y a]\ny)))]\nx))"),
+            "(defn make [a]\n  (let [x (reify P\n          (m [this]\n            (let [y a]\n              y)))]\n    x))",
+        );
+        assert_eq!(
+            xformat("(letfn [(f [x]\n(let [y x]\n(h y\nz)))]\n(f 1))"),
+            "(letfn [(f [x]\n          (let [y x]\n            (h y\n               z)))]\n  (f 1))",
+        );
+    }
+
+    #[test]
+    fn namespaces() {
+        let ns = "(ns a (:require [clojure.core.async :as a :refer [go-loop] :rename {go gx}]))\n";
+        let source = format!("{}(a/go-loop [x 1]\n(recur x))\n(go-loop [x 1]\n(recur x))\n(gx\n(f))", ns);
+        let expected = format!("{}(a/go-loop [x 1]\n  (recur x))\n(go-loop [x 1]\n  (recur x))\n(gx\n  (f))", ns);
+        assert_eq!(xformat(&source), expected);
+
+        // A rule for the qualified name only applies to the macro of that library
+        let mut rules = Rules::default();
+        rules.remove("go-loop");
+        rules.insert("clojure.core.async/go-loop", Rule(vec![Indent::Block(1)]));
+        assert_eq!(with_rules(&source, rules.clone()), expected);
+        let source = "(ns a (:require [my.lib :as a :refer [go-loop]]))\n(a/go-loop [x 1]\n(recur x))\n(go-loop [x]\nx)";
+        assert_eq!(
+            with_rules(source, rules),
+            "(ns a (:require [my.lib :as a :refer [go-loop]]))\n(a/go-loop [x 1]\n           (recur x))\n(go-loop [x]\n         x)",
+        );
+    }
+
+    #[test]
+    fn patterns() {
+        // No patterns by default
+        assert_eq!(xformat("(with-open [r (f)]\n(g r))"), "(with-open [r (f)]\n           (g r))");
+        assert_eq!(xformat("(defthing a\nb)"), "(defthing a\n          b)");
+
+        let mut rules = Rules::default();
+        rules.insert_pattern(Pattern::Prefix("def".to_owned()), Rule(vec![Indent::Inner(0, None)]));
+        assert_eq!(with_rules("(defthing a\nb)", rules.clone()), "(defthing a\n  b)");
+        // Only the name is matched, not the namespace
+        let ns = "(ns a (:require [defaults.core :as dc]))\n";
+        assert_eq!(with_rules(&format!("{}(dc/merge a\nb)", ns), rules.clone()), format!("{}(dc/merge a\n          b)", ns));
+        assert_eq!(with_rules(&format!("{}(dc/defthing a\nb)", ns), rules), format!("{}(dc/defthing a\n  b)", ns));
+
+        // A regex keeps other names starting with `def` function calls, a bare name wins over it
+        let mut rules = Rules::default();
+        rules.insert_pattern(Pattern::Regex(Regex::new("^def[a-z-]*$").unwrap()), Rule(vec![Indent::Inner(0, None)]));
+        rules.insert("default", Rule::default());
+        assert_eq!(with_rules("(defthing a\nb)", rules.clone()), "(defthing a\n  b)");
+        assert_eq!(with_rules("(deref a\nb)", rules.clone()), "(deref a\n       b)");
+        assert_eq!(with_rules("(default a\nb)", rules.clone()), "(default a\n         b)");
+        assert_eq!(with_rules("(def->thing a\nb)", rules), "(def->thing a\n            b)");
+    }
+
+    #[test]
+    fn custom_rules() {
+        let mut rules = Rules::default();
+        rules.insert("my-let", Rule(vec![Indent::Block(1)]));
+        rules.insert("foo", Rule(vec![Indent::Inner(0, None)]));
+        rules.insert("let", Rule::default());
+        assert_eq!(with_rules("(my-let [a 1]\na)", rules.clone()), "(my-let [a 1]\n  a)");
+        assert_eq!(with_rules("(foo a\nb)", rules.clone()), "(foo a\n  b)");
+        assert_eq!(with_rules("(let [a 1]\na)", rules.clone()), "(let [a 1]\n     a)");
+
+        // The special arguments as in a block, the others aligned with them
+        rules.insert("my-cond->", Rule(vec![Indent::List(1)]));
+        assert_eq!(with_rules("(my-cond-> x\na (f)\nb (g))", rules.clone()), "(my-cond-> x\n           a (f)\n           b (g))");
+        assert_eq!(with_rules("(my-cond->\nx\na (f))", rules.clone()), "(my-cond->\n    x\n    a (f))");
+
+        rules.remove("foo");
+        assert_eq!(with_rules("(foo a\nb)", rules), "(foo a\n     b)");
+        assert_eq!(with_rules("(if a\nb)", Rules::empty()), "(if a\n    b)");
+    }
+}
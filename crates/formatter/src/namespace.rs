@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use tokenizer::Tokenizer;
+use tokenizer::dialect::Dialect;
+use tokenizer::symbol::parse_symbol;
+use tokenizer::token::TokenType;
+use tokenizer::tree::TokenTree;
+
+/// Aliases and referred names of the `ns` form, to resolve symbols to their namespace
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Namespace {
+    /// Namespaces by their alias
+    aliases: HashMap<String, String>,
+    /// Fully qualified names by the referred name, e.g. `clojure.core.async/go-loop` by `go-loop`
+    refers: HashMap<String, String>,
+}
+
+fn is_symbol(tree: &TokenTree) -> bool {
+    matches!(tree, TokenTree::Leaf(token) if token.kind() == TokenType::Symbol)
+}
+
+/// The name of a lib, a symbol or a string for JavaScript modules
+fn lib_name<'a>(tree: &TokenTree<'a>) -> Option<&'a str> {
+    match tree {
+        TokenTree::Leaf(token) if token.kind() == TokenType::Symbol => Some(token.value()),
+        TokenTree::Leaf(token) if token.kind() == TokenType::String => Some(token.value().trim_matches('"')),
+        _ => None,
+    }
+}
+
+/// The forms read on any platform: both branches of reader conditionals are kept,
+/// discarded forms and metadata are dropped
+fn forms<'t, 'a>(trees: &'t [TokenTree<'a>]) -> Vec<&'t TokenTree<'a>> {
+    let mut out = Vec::new();
+    let mut trees = trees.iter();
+    while let Some(tree) = trees.next() {
+        let TokenTree::Leaf(token) = tree else {
+            out.push(tree);
+            continue;
+        };
+        match token.kind() {
+            // The discarded form, or the metadata
+            TokenType::Discard | TokenType::Metadata => {
+                trees.next();
+            },
+            TokenType::ReaderConditional | TokenType::ReaderConditionalSplicing => {
+                let Some(branches) = trees.next() else {
+                    break;
+                };
+                for form in forms(branches.children()).into_iter().skip(1).step_by(2) {
+                    if token.kind() == TokenType::ReaderConditionalSplicing {
+                        out.extend(forms(form.children()));
+                    } else {
+                        out.push(form);
+                    }
+                }
+            },
+            _ => out.push(tree),
+        }
+    }
+    out
+}
+
+impl Namespace {
+    /// Reads the first `ns` form of the source. Without one nothing resolves
+    pub fn from_source(source: &str, dialect: Dialect) -> Namespace {
+        let tokens = Tokenizer::from(source).with_dialect(dialect).filter(|token| !token.is_trivia());
+        let trees = TokenTree::build(tokens);
+        let mut namespace = Namespace::default();
+
+        let ns = trees.iter().find(|tree| {
+            tree.first().kind() == TokenType::LParen
+                && tree.children().first().is_some_and(|head| head.first().value() == "ns")
+        });
+        if let Some(ns) = ns {
+            for clause in forms(ns.children()).into_iter().filter(|clause| clause.first().kind() == TokenType::LParen) {
+                let args = forms(clause.children());
+                let Some((head, libspecs)) = args.split_first() else {
+                    continue;
+                };
+                match head.first().value() {
+                    ":require" | ":require-macros" | ":use" | ":use-macros" => {
+                        for libspec in libspecs {
+                            namespace.libspec(libspec, None);
+                        }
+                    },
+                    _ => (),
+                }
+            }
+        }
+        namespace
+    }
+
+    /// `[lib :as alias :refer [names]]`, or a prefix list `(prefix lib [lib :as alias])`
+    fn libspec(&mut self, libspec: &TokenTree, prefix: Option<&str>) {
+        let args = forms(libspec.children());
+        let Some(lib) = args.first().and_then(|lib| lib_name(lib)) else {
+            return;
+        };
+        let lib = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, lib),
+            None => lib.to_owned(),
+        };
+
+        // A prefix list
+        if args.get(1).is_some_and(|arg| arg.first().kind() != TokenType::Keyword) {
+            for libspec in &args[1..] {
+                if !is_symbol(libspec) {
+                    self.libspec(libspec, Some(&lib));
+                }
+            }
+            return;
+        }
+
+        // Renamed names are only referred by their new names
+        let mut renamed = Vec::new();
+        for option in args[1..].chunks(2) {
+            let [key, value] = option else {
+                break;
+            };
+            match key.first().value() {
+                ":as" | ":as-alias" => {
+                    if let Some(alias) = lib_name(value) {
+                        self.aliases.insert(alias.to_owned(), lib.clone());
+                    }
+                },
+                ":refer" | ":only" | ":refer-macros" => {
+                    for name in forms(value.children()).into_iter().filter(|name| is_symbol(name)) {
+                        let name = name.first().value();
+                        self.refers.insert(name.to_owned(), format!("{}/{}", lib, name));
+                    }
+                },
+                ":rename" => {
+                    let names = forms(value.children());
+                    for pair in names.chunks(2) {
+                        if let [from, to] = pair {
+                            if from.first().value() != to.first().value() {
+                                renamed.push(from.first().value());
+                            }
+                            self.refers.insert(to.first().value().to_owned(), format!("{}/{}", lib, from.first().value()));
+                        }
+                    }
+                },
+                _ => (),
+            }
+        }
+        for name in renamed {
+            if self.refers.get(name).is_some_and(|qualified| *qualified == format!("{}/{}", lib, name)) {
+                self.refers.remove(name);
+            }
+        }
+    }
+
+    /// The fully qualified name of the symbol, if it has a namespace or is referred.
+    /// A namespace which is not an alias is kept as written
+    pub fn resolve(&self, symbol: &str) -> Option<String> {
+        let symbol = parse_symbol(symbol).ok()?;
+        match symbol.namespace {
+            Some(namespace) => {
+                let namespace = self.aliases.get(namespace).map_or(namespace, String::as_str);
+                Some(format!("{}/{}", namespace, symbol.name))
+            },
+            None => self.refers.get(symbol.name).cloned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokenizer::dialect::Dialect;
+
+    use super::Namespace;
+
+    fn resolve(ns: &str, symbol: &str) -> Option<String> {
+        Namespace::from_source(ns, Dialect::Clojure).resolve(symbol)
+    }
+
+    #[test]
+    fn aliases() {
+        let ns = "(ns a.b (:require [clojure.string :as str] [clojure.spec.alpha :as-alias s] \"c\"))";
+        assert_eq!(resolve(ns, "str/join").as_deref(), Some("clojure.string/join"));
+        assert_eq!(resolve(ns, "s/fdef").as_deref(), Some("clojure.spec.alpha/fdef"));
+        assert_eq!(resolve(ns, "other.ns/f").as_deref(), Some("other.ns/f"));
+        assert_eq!(resolve(ns, "join"), None);
+        assert_eq!(resolve(ns, "a/"), None);
+    }
+
+    #[test]
+    fn refers() {
+        let ns = "(ns a (:require [clojure.core.async :as a :refer [go-loop <!]] [x.y :refer :all]))";
+        assert_eq!(resolve(ns, "go-loop").as_deref(), Some("clojure.core.async/go-loop"));
+        assert_eq!(resolve(ns, "<!").as_deref(), Some("clojure.core.async/<!"));
+        assert_eq!(resolve(ns, "a/go").as_deref(), Some("clojure.core.async/go"));
+        // The names referred by `:all` are unknown
+        assert_eq!(resolve(ns, "z"), None);
+    }
+
+    #[test]
+    fn rename() {
+        let ns = "(ns a (:require [clojure.core.async :refer [go-loop] :rename {go-loop gl}]))";
+        assert_eq!(resolve(ns, "gl").as_deref(), Some("clojure.core.async/go-loop"));
+        assert_eq!(resolve(ns, "go-loop"), None);
+    }
+
+    #[test]
+    fn prefix_lists() {
+        let ns = "(ns a (:require (clojure [string :as str] [set :refer [union]]) [a [b :as x] c.d]))";
+        assert_eq!(resolve(ns, "str/join").as_deref(), Some("clojure.string/join"));
+        assert_eq!(resolve(ns, "union").as_deref(), Some("clojure.set/union"));
+        assert_eq!(resolve(ns, "x/f").as_deref(), Some("a.b/f"));
+    }
+
+    #[test]
+    fn use_and_reader_conditionals() {
+        let ns = "(ns a (:use [clojure.core.async :only [go-loop]] clojure.test) #?(:clj (:require [b :as c])))";
+        assert_eq!(resolve(ns, "go-loop").as_deref(), Some("clojure.core.async/go-loop"));
+        assert_eq!(resolve(ns, "c/d").as_deref(), Some("b/d"));
+        assert_eq!(resolve(ns, "is"), None);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(Namespace::from_source("", Dialect::Clojure), Namespace::default());
+        assert_eq!(Namespace::from_source("(ns)", Dialect::Clojure), Namespace::default());
+        assert_eq!(Namespace::from_source("(ns a (:require) (:require [] [:as x] [b :as]))", Dialect::Clojure), Namespace::default());
+        assert_eq!(Namespace::from_source("(defn f [])", Dialect::Clojure), Namespace::default());
+        assert_eq!(resolve("(ns a (:require [b :as c]", "c/d").as_deref(), Some("b/d"));
+        assert_eq!(resolve("(ns a (:require [b :as c] [d :refer [e", "e").as_deref(), Some("d/e"));
+        assert_eq!(resolve("(ns a (:require [b :as c]))) ]", "c/d").as_deref(), Some("b/d"));
+    }
+}
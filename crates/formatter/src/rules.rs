@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use regex::Regex;
+use tokenizer::symbol::parse_symbol;
+
+use crate::namespace::Namespace;
+
+/// A part of a rule, after cljfmt
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Indent {
+    /// The number of special arguments, e.g. the bindings of `let`, followed by
+    /// the body indented by two spaces
+    Block(usize),
+    /// The number of special arguments, indented as those of `Block`, followed by the other
+    /// arguments aligned with them as in a function call
+    List(usize),
+    /// Lists at the depth inside the form have every argument indented by two
+    /// spaces, as in `defn`. Depth 0 is the form itself. With an index, only the
+    /// lists inside the argument at the index count, e.g. 0 for the bindings of `letfn`
+    Inner(usize, Option<usize>),
+}
+
+/// Indentation of a list by the symbol at its head. A list without an indent
+/// of its own is indented as a function call
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Rule(pub Vec<Indent>);
+
+impl Rule {
+    /// Indentation of the form itself
+    pub(crate) fn own(&self) -> Option<Indent> {
+        self.0.iter().copied().find(|indent| matches!(indent, Indent::Block(_) | Indent::List(_) | Indent::Inner(0, None)))
+    }
+
+    /// True if the rule applies to forms nested in the list
+    pub(crate) fn is_nested(&self) -> bool {
+        self.0.iter().any(|indent| matches!(indent, Indent::Inner(depth, _) if *depth > 0))
+    }
+}
+
+/// Indents separated by commas, each `block N`, `list N`, `inner` or `inner DEPTH [INDEX]`,
+/// e.g. `block 1, inner 2 0`. `fn` is the function call indentation
+impl FromStr for Rule {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "fn" {
+            return Ok(Rule::default());
+        }
+        let number = |n: &str| n.parse().map_err(|_| ());
+        s.split(',')
+            .map(|indent| match indent.split_whitespace().collect::<Vec<_>>()[..] {
+                ["block", n] => Ok(Indent::Block(number(n)?)),
+                ["list", n] => Ok(Indent::List(number(n)?)),
+                ["inner"] => Ok(Indent::Inner(0, None)),
+                ["inner", depth] => Ok(Indent::Inner(number(depth)?, None)),
+                ["inner", depth, index] => Ok(Indent::Inner(number(depth)?, Some(number(index)?))),
+                _ => Err(()),
+            })
+            .collect::<Result<_, _>>()
+            .map(Rule)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("fn");
+        }
+        for (i, indent) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            match indent {
+                Indent::Block(n) => write!(f, "block {}", n)?,
+                Indent::List(n) => write!(f, "list {}", n)?,
+                Indent::Inner(depth, None) => write!(f, "inner {}", depth)?,
+                Indent::Inner(depth, Some(index)) => write!(f, "inner {} {}", depth, index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Matches the symbols of a pattern rule
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Prefix(String),
+    /// Matches anywhere in the symbol unless anchored, e.g. `-let$`
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn is_match(&self, symbol: &str) -> bool {
+        match self {
+            Pattern::Prefix(prefix) => symbol.starts_with(prefix.as_str()),
+            Pattern::Regex(regex) => regex.is_match(symbol),
+        }
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Pattern::Prefix(a), Pattern::Prefix(b)) => a == b,
+            (Pattern::Regex(a), Pattern::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Pattern {}
+
+const BLOCK_0: &[Indent] = &[Indent::Block(0)];
+const BLOCK_1: &[Indent] = &[Indent::Block(1)];
+const BLOCK_2: &[Indent] = &[Indent::Block(2)];
+const INNER: &[Indent] = &[Indent::Inner(0, None)];
+
+const DEFAULT_RULES: &[(&str, &[Indent])] = &[
+    ("alt!!", BLOCK_0),
+    ("alt!", BLOCK_0),
+    ("comment", BLOCK_0),
+    ("cond", BLOCK_0),
+    ("delay", BLOCK_0),
+    ("do", BLOCK_0),
+    ("finally", BLOCK_0),
+    ("future", BLOCK_0),
+    ("go", BLOCK_0),
+    ("thread", BLOCK_0),
+    ("try", BLOCK_0),
+
+    ("ns", BLOCK_1),
+    ("if", BLOCK_1),
+    ("if-not", BLOCK_1),
+    ("case", BLOCK_1),
+    ("when", BLOCK_1),
+    ("while", BLOCK_1),
+    ("cond->", BLOCK_1),
+    ("cond->>", BLOCK_1),
+    ("when-not", BLOCK_1),
+    ("when-first", BLOCK_1),
+    ("doto", BLOCK_1),
+    ("locking", BLOCK_1),
+    ("fdef", BLOCK_1),
+    ("extend", BLOCK_1),
+    ("let", BLOCK_1),
+    ("binding", BLOCK_1),
+    ("loop", BLOCK_1),
+    ("for", BLOCK_1),
+    ("doseq", BLOCK_1),
+    ("dotimes", BLOCK_1),
+    ("when-let", BLOCK_1),
+    ("if-let", BLOCK_1),
+    ("when-some", BLOCK_1),
+    ("if-some", BLOCK_1),
+    ("this-as", BLOCK_1),
+    ("testing", BLOCK_1),
+    ("async", BLOCK_1),
+    ("go-loop", BLOCK_1),
+
+    ("condp", BLOCK_2),
+    ("as->", BLOCK_2),
+    ("catch", BLOCK_2),
+    ("are", BLOCK_2),
+
+    ("fn", INNER),
+    ("def", INNER),
+    ("defn", INNER),
+    ("bound-fn", INNER),
+    ("defmethod", INNER),
+    ("run", INNER),
+    ("run*", INNER),
+    ("fresh", INNER),
+    ("deftest", INNER),
+    ("use-fixtures", INNER),
+
+    ("reify", &[Indent::Inner(0, None), Indent::Inner(1, None)]),
+    ("proxy", &[Indent::Block(2), Indent::Inner(1, None)]),
+    ("deftype", &[Indent::Block(2), Indent::Inner(1, None)]),
+    ("defrecord", &[Indent::Block(2), Indent::Inner(1, None)]),
+    ("defprotocol", &[Indent::Block(1), Indent::Inner(1, None)]),
+    ("definterface", &[Indent::Block(1), Indent::Inner(1, None)]),
+    ("extend-protocol", &[Indent::Block(1), Indent::Inner(1, None)]),
+    ("extend-type", &[Indent::Block(1), Indent::Inner(1, None)]),
+    ("specify", &[Indent::Block(1), Indent::Inner(1, None)]),
+    ("specify!", &[Indent::Block(1), Indent::Inner(1, None)]),
+    ("letfn", &[Indent::Block(1), Indent::Inner(2, Some(0))]),
+];
+
+/// Rules by the symbol. Lists headed by other symbols, keywords or numbers
+/// are indented as function calls.
+///
+/// A symbol may be fully qualified, e.g. `compojure.core/defroutes`, to tell apart
+/// macros of the same name from different libraries. Pattern rules match the name
+/// of symbols by a prefix or a regex, e.g. every `def…` macro.
+///
+/// The default has the built-in rules and no patterns, `Rules::empty()` has none.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rules {
+    by_symbol: HashMap<String, Rule>,
+    /// Tried in order
+    patterns: Vec<(Pattern, Rule)>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            by_symbol: DEFAULT_RULES.iter().map(|&(symbol, indents)| (symbol.to_owned(), Rule(indents.to_vec()))).collect(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl Rules {
+    pub fn empty() -> Self {
+        Rules { by_symbol: HashMap::new(), patterns: Vec::new() }
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Rule> {
+        self.by_symbol.get(symbol)
+    }
+
+    /// The rule of the symbol at the head of a list, looked up by
+    ///
+    /// 1. the fully qualified name, with the aliases and referred names of the namespace
+    ///    resolved, e.g. `clojure.spec.alpha/fdef` for `s/fdef`
+    /// 2. the bare name, e.g. `fdef`
+    /// 3. the first pattern matching the bare name, so the namespace never matches
+    pub fn resolve(&self, symbol: &str, namespace: &Namespace) -> Option<&Rule> {
+        let qualified = namespace.resolve(symbol);
+        let name = parse_symbol(qualified.as_deref().unwrap_or(symbol)).map_or(symbol, |symbol| symbol.name);
+        qualified
+            .as_deref()
+            .and_then(|qualified| self.get(qualified))
+            .or_else(|| self.get(name))
+            .or_else(|| self.patterns.iter().find(|(pattern, _)| pattern.is_match(name)).map(|(_, rule)| rule))
+    }
+
+    /// Adds the rule or overrides the existing one, which is returned
+    pub fn insert(&mut self, symbol: impl Into<String>, rule: Rule) -> Option<Rule> {
+        self.by_symbol.insert(symbol.into(), rule)
+    }
+
+    pub fn remove(&mut self, symbol: &str) -> Option<Rule> {
+        self.by_symbol.remove(symbol)
+    }
+
+    /// Adds the pattern rule, which is tried before the existing ones
+    pub fn insert_pattern(&mut self, pattern: Pattern, rule: Rule) {
+        self.patterns.insert(0, (pattern, rule));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use tokenizer::dialect::Dialect;
+
+    use super::{Indent, Pattern, Rule, Rules};
+    use crate::namespace::Namespace;
+
+    fn rule(s: &str) -> Result<Rule, ()> {
+        s.parse()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(rule("fn"), Ok(Rule(vec![])));
+        assert_eq!(rule("block 0"), Ok(Rule(vec![Indent::Block(0)])));
+        assert_eq!(rule("inner"), Ok(Rule(vec![Indent::Inner(0, None)])));
+        assert_eq!(rule(" block 1 ,inner 2 0"), Ok(Rule(vec![Indent::Block(1), Indent::Inner(2, Some(0))])));
+        assert_eq!(rule("inner 1"), Ok(Rule(vec![Indent::Inner(1, None)])));
+        assert_eq!(rule("list 2"), Ok(Rule(vec![Indent::List(2)])));
+        assert_eq!(rule(""), Err(()));
+        assert_eq!(rule("block"), Err(()));
+        assert_eq!(rule("list"), Err(()));
+        assert_eq!(rule("block -1"), Err(()));
+        assert_eq!(rule("inner 1 2 3"), Err(()));
+        assert_eq!(rule("block 1,"), Err(()));
+        assert_eq!(rule("fn, block 1"), Err(()));
+    }
+
+    #[test]
+    fn display() {
+        for s in ["fn", "block 2", "list 1", "inner 0", "block 1, inner 2 0"] {
+            assert_eq!(rule(s).unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn own_and_nested() {
+        assert_eq!(rule("block 1, inner 2 0").unwrap().own(), Some(Indent::Block(1)));
+        assert_eq!(rule("inner 1, inner 0").unwrap().own(), Some(Indent::Inner(0, None)));
+        assert_eq!(rule("inner 1").unwrap().own(), None);
+        assert_eq!(rule("fn").unwrap().own(), None);
+        assert!(rule("block 2, inner 1").unwrap().is_nested());
+        assert!(!rule("inner 0").unwrap().is_nested());
+    }
+
+    #[test]
+    fn registry() {
+        let mut rules = Rules::default();
+        assert_eq!(rules.get("let"), Some(&Rule(vec![Indent::Block(1)])));
+        assert_eq!(rules.get("defn"), Some(&Rule(vec![Indent::Inner(0, None)])));
+        assert_eq!(rules.get("foo"), None);
+
+        assert_eq!(rules.insert("let", Rule(vec![Indent::Block(2)])), Some(Rule(vec![Indent::Block(1)])));
+        assert_eq!(rules.get("let"), Some(&Rule(vec![Indent::Block(2)])));
+        assert_eq!(rules.remove("let"), Some(Rule(vec![Indent::Block(2)])));
+        assert_eq!(rules.get("let"), None);
+        assert_eq!(Rules::empty().get("do"), None);
+    }
+
+    #[test]
+    fn resolve() {
+        let namespace = Namespace::from_source("(ns a (:require [defaults.core :as dc] [b :refer [go]]))", Dialect::Clojure);
+        let mut rules = Rules::default();
+        rules.insert("defaults.core/merge", rule("block 1").unwrap());
+        rules.insert("merge", rule("block 2").unwrap());
+        rules.insert_pattern(Pattern::Prefix("def".to_owned()), rule("inner").unwrap());
+        rules.insert_pattern(Pattern::Regex(Regex::new("-let$").unwrap()), rule("block 1").unwrap());
+
+        // The qualified name before the bare one, the bare one before patterns
+        assert_eq!(rules.resolve("dc/merge", &namespace), Some(&Rule(vec![Indent::Block(1)])));
+        assert_eq!(rules.resolve("merge", &namespace), Some(&Rule(vec![Indent::Block(2)])));
+        assert_eq!(rules.resolve("other/merge", &namespace), Some(&Rule(vec![Indent::Block(2)])));
+        assert_eq!(rules.resolve("go", &namespace), Some(&Rule(vec![Indent::Block(0)])));
+        assert_eq!(rules.resolve("defn", &namespace), Some(&Rule(vec![Indent::Inner(0, None)])));
+
+        // Patterns match the name only
+        assert_eq!(rules.resolve("dc/defthing", &namespace), Some(&Rule(vec![Indent::Inner(0, None)])));
+        assert_eq!(rules.resolve("dc/assoc", &namespace), None);
+        assert_eq!(rules.resolve("defaults.core/assoc", &namespace), None);
+        assert_eq!(rules.resolve("x/when-let", &namespace), Some(&Rule(vec![Indent::Block(1)])));
+
+        // The last inserted pattern is tried first
+        assert_eq!(rules.resolve("def-let", &namespace), Some(&Rule(vec![Indent::Block(1)])));
+        assert_eq!(Rules::default().resolve("defthing", &namespace), None);
+        assert_eq!(Rules::default().resolve("with-open", &namespace), None);
+    }
+}
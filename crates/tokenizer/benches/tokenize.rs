@@ -0,0 +1,44 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use tokenizer::Tokenizer;
+
+/// A namespace mixing the common token kinds, including non-ASCII text
+fn source() -> String {
+    let form = r#"(defn process-request
+  "Handles the request. Обрабатывает запрос."
+  [{:keys [headers body] :as request} & {:keys [timeout] :or {timeout 1000}}]
+  ;; Comments are frequent in real code, so they are in the benchmark too
+  (let [parsed (json/parse-string body true)
+        id     (get-in parsed [:data :id])
+        total  (reduce + 0.5 (map #(* % 22/7) (range 100)))]
+    (when-not (str/blank? id)
+      (swap! state assoc-in [::requests id] {:at #inst "2024-01-01" :total total}))
+    (println \a \newline 0xFF 'quoted `(~@body) @state #"re\d+")))
+
+"#;
+    form.repeat(2000)
+}
+
+/// Generated EDN data with long strings, comments and indentation
+fn data() -> String {
+    let entry = r#"    ;; Generated entry, do not edit by hand. Сгенерировано автоматически.
+    {:description "A long description of the entry which is mostly plain text, as generated data often is"
+     :identifier  "3f2b8c1e-5d4a-4e7f-9a6b-0c1d2e3f4a5b"}
+"#;
+    format!("[\n{}]\n", entry.repeat(5000))
+}
+
+fn tokenize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tokenize");
+
+    for (name, source) in [("namespace", source()), ("data", data())] {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| Tokenizer::from(black_box(source.as_str())).count())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, tokenize);
+criterion_main!(benches);
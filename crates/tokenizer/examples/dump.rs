@@ -1,19 +1,34 @@
 use std::fs::read_to_string;
+#[cfg(feature = "serde")]
+use std::io::stdout;
+#[cfg(feature = "serde")]
+use tokenizer::json;
 use tokenizer::Tokenizer;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
-    let filename = args[1].to_owned();
+    let (format, filename) = match args.len() {
+        3 => (args[1].as_str(), args[2].to_owned()),
+        _ => ("", args[1].to_owned()),
+    };
     
-    let mut token_count = 0;
     let data = read_to_string(filename).unwrap();
 
-    let mut tokenizer = Tokenizer::from(&*data);
-
-    while let Some(token) = tokenizer.next() {
-        println!("{:?}", token);
-        token_count += 1;
-    };
-    
-    println!("{}", token_count);
+    match format {
+        #[cfg(feature = "serde")]
+        "--json" => json::write_json(&mut stdout().lock(), Tokenizer::from(&*data)).unwrap(),
+        #[cfg(feature = "serde")]
+        "--jsonl" => json::write_jsonl(&mut stdout().lock(), Tokenizer::from(&*data)).unwrap(),
+        #[cfg(not(feature = "serde"))]
+        "--json" | "--jsonl" => eprintln!("{} needs the `serde` feature", format),
+        _ => {
+            let mut token_count = 0;
+            for token in Tokenizer::from(&*data) {
+                println!("{:?}", token);
+                token_count += 1;
+            }
+            
+            println!("{}", token_count);
+        }
+    }
 }
@@ -2,33 +2,19 @@ use std::{fs::read_to_string, path::Path};
 use walkdir::WalkDir;
 use std::collections::HashMap;
 use tokenizer::Tokenizer;
-use tokenizer::token::{Token, TokenType};
-
-#[derive(PartialEq, Eq, Hash, Debug)]
-struct OwnedToken {
-    data: String,
-    kind: TokenType
-}
-
-impl<'a> From<Token<'a>> for OwnedToken {
-    fn from(value: Token) -> Self {
-        OwnedToken {
-            data: value.value().to_owned(),
-            kind: value.kind()
-        }
-    }
-}
+use tokenizer::token::{OwnedToken, Token, TokenType};
 
 fn compute_cnt(toks: &mut HashMap<OwnedToken, usize>, file: &Path) -> usize {
-    let mut cnt = 0;
     let data = read_to_string(file).unwrap();
-    let mut tokenizer = Tokenizer::from(&*data);
-    while let Some(token) = tokenizer.next() {
+    let mut cnt = 0;
+    for token in Tokenizer::from(&*data) {
+        // Tokens are counted by kind and text, regardless of their place
+        let token = Token::new(token.value(), token.kind());
         toks.entry(OwnedToken::from(token))
             .and_modify(|v| *v += 1)
             .or_insert(1);
         cnt += 1;
-    };
+    }
     cnt
 }
 
@@ -50,13 +36,13 @@ fn main() {
     kvs.sort_unstable_by_key(|(_, v)| **v);
     let kvs: Vec<(&OwnedToken, &usize)> = kvs.iter()
         .rev()
-        .filter(|(tok, _)| tok.kind != TokenType::Whitespace)
+        .filter(|(token, _)| token.kind() != TokenType::Whitespace)
         .take(40)
         .cloned()
         .collect();
     
     for (tok, &cnt) in &kvs {
-        println!("{}\t\t{:?}", cnt, **tok);
+        println!("{}\t\t{:?}\t{:?}", cnt, tok.kind(), tok.value());
     }
     
     println!("{}", cnt);
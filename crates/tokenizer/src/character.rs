@@ -0,0 +1,109 @@
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CharacterError {
+    /// Neither a single char nor a known name, e.g. `\foo`
+    Unsupported,
+    /// Malformed `\uXXXX` escape or a surrogate code point
+    InvalidUnicode,
+    /// Malformed `\oNNN` escape or a value above `\o377`
+    InvalidOctal,
+}
+
+impl fmt::Display for CharacterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CharacterError::Unsupported => write!(f, "unsupported character"),
+            CharacterError::InvalidUnicode => write!(f, "invalid unicode character"),
+            CharacterError::InvalidOctal => write!(f, "invalid octal escape, expected \\o0 to \\o377"),
+        }
+    }
+}
+
+impl std::error::Error for CharacterError {}
+
+fn named(name: &str) -> Option<char> {
+    match name {
+        "newline" => Some('\n'),
+        "space" => Some(' '),
+        "tab" => Some('\t'),
+        "formfeed" => Some('\u{000c}'),
+        "backspace" => Some('\u{0008}'),
+        "return" => Some('\r'),
+        _ => None,
+    }
+}
+
+fn unicode(digits: &str) -> Result<char, CharacterError> {
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(CharacterError::InvalidUnicode);
+    }
+
+    let code = u32::from_str_radix(digits, 16).map_err(|_| CharacterError::InvalidUnicode)?;
+    // Surrogates are rejected by `char::from_u32`
+    char::from_u32(code).ok_or(CharacterError::InvalidUnicode)
+}
+
+fn octal(digits: &str) -> Result<char, CharacterError> {
+    if digits.len() > 3 || !digits.chars().all(|c| c.is_digit(8)) {
+        return Err(CharacterError::InvalidOctal);
+    }
+
+    let code = u32::from_str_radix(digits, 8).map_err(|_| CharacterError::InvalidOctal)?;
+    if code > 0o377 {
+        return Err(CharacterError::InvalidOctal);
+    }
+    char::from_u32(code).ok_or(CharacterError::InvalidOctal)
+}
+
+/// Decodes a character literal including the leading `\`
+pub fn parse(s: &str) -> Result<char, CharacterError> {
+    let body = s.strip_prefix('\\').ok_or(CharacterError::Unsupported)?;
+    let mut chars = body.chars();
+
+    match (chars.next(), chars.as_str()) {
+        (Some(c), "") => Ok(c),
+        (Some('u'), digits) => unicode(digits),
+        (Some('o'), digits) => octal(digits),
+        _ => named(body).ok_or(CharacterError::Unsupported),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, CharacterError};
+
+    #[test]
+    fn valid() {
+        assert_eq!(parse("\\a"), Ok('a'));
+        assert_eq!(parse("\\u"), Ok('u'));
+        assert_eq!(parse("\\o"), Ok('o'));
+        assert_eq!(parse("\\ы"), Ok('ы'));
+        assert_eq!(parse("\\("), Ok('('));
+        assert_eq!(parse("\\;"), Ok(';'));
+        assert_eq!(parse("\\\""), Ok('"'));
+        assert_eq!(parse("\\newline"), Ok('\n'));
+        assert_eq!(parse("\\space"), Ok(' '));
+        assert_eq!(parse("\\tab"), Ok('\t'));
+        assert_eq!(parse("\\formfeed"), Ok('\u{c}'));
+        assert_eq!(parse("\\backspace"), Ok('\u{8}'));
+        assert_eq!(parse("\\return"), Ok('\r'));
+        assert_eq!(parse("\\u0041"), Ok('A'));
+        assert_eq!(parse("\\u03bb"), Ok('λ'));
+        assert_eq!(parse("\\o101"), Ok('A'));
+        assert_eq!(parse("\\o7"), Ok('\u{7}'));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(parse("\\foo"), Err(CharacterError::Unsupported));
+        assert_eq!(parse("\\Newline"), Err(CharacterError::Unsupported));
+        assert_eq!(parse("\\u12"), Err(CharacterError::InvalidUnicode));
+        assert_eq!(parse("\\u12345"), Err(CharacterError::InvalidUnicode));
+        assert_eq!(parse("\\uGGGG"), Err(CharacterError::InvalidUnicode));
+        assert_eq!(parse("\\uD800"), Err(CharacterError::InvalidUnicode));
+        assert_eq!(parse("\\o400"), Err(CharacterError::InvalidOctal));
+        assert_eq!(parse("\\o1234"), Err(CharacterError::InvalidOctal));
+        assert_eq!(parse("\\o8"), Err(CharacterError::InvalidOctal));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::whitespace;
 
 /// Characters with special meaning, but they can be a part of a symbol/keyword
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum SimpleControl {
     /// The `#` symbol (dispatch)
     Hash,
@@ -8,12 +9,14 @@ pub enum SimpleControl {
     Quote,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ComplexControl {
     /// The ':' symbol (keyword)
     Colon,
 }
 
 /// Characters with special meaning which can't be a part of a symbol/keyword
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum SimpleStructural {
     /// The `(` symbol
     LPar,
@@ -35,6 +38,7 @@ pub enum SimpleStructural {
     At,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ComplexStructural {
     /// The `;` symbol (comment)
     Semicolon,
@@ -46,7 +50,7 @@ pub enum ComplexStructural {
     Tilde
 }
 
-pub fn parse_simple_control(c: char) -> Option<SimpleControl> {
+pub const fn parse_simple_control(c: char) -> Option<SimpleControl> {
     match c {
         '#' => Some(SimpleControl::Hash),
         '\'' => Some(SimpleControl::Quote),
@@ -54,14 +58,14 @@ pub fn parse_simple_control(c: char) -> Option<SimpleControl> {
     }
 }
 
-pub fn parse_complex_control(c: char) -> Option<ComplexControl> {
+pub const fn parse_complex_control(c: char) -> Option<ComplexControl> {
     match c {
         ':' => Some(ComplexControl::Colon),
         _ => None
     }
 }
 
-pub fn parse_simple_structural(c: char) -> Option<SimpleStructural> {
+pub const fn parse_simple_structural(c: char) -> Option<SimpleStructural> {
     match c {
         '(' => Some(SimpleStructural::LPar),
         ')' => Some(SimpleStructural::RPar),
@@ -76,7 +80,7 @@ pub fn parse_simple_structural(c: char) -> Option<SimpleStructural> {
     }
 }
 
-pub fn parse_complex_structural(c: char) -> Option<ComplexStructural> {
+pub const fn parse_complex_structural(c: char) -> Option<ComplexStructural> {
    match c { 
         ';' => Some(ComplexStructural::Semicolon),
         '\\' => Some(ComplexStructural::Backslash),
@@ -86,6 +90,7 @@ pub fn parse_complex_structural(c: char) -> Option<ComplexStructural> {
    }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Char {
     Whitespace,
     SimpleControl(SimpleControl),
@@ -95,7 +100,8 @@ pub enum Char {
     Regular
 }
 
-pub fn parse(c: char) -> Char {
+/// Classifies a char by walking all the classes
+const fn classify(c: char) -> Char {
     if whitespace::is_clojure_whitespace(c) {
         return Char::Whitespace;
     }
@@ -119,38 +125,62 @@ pub fn parse(c: char) -> Char {
     Char::Regular
 }
 
-pub fn is_simple_structural(c: char) -> bool {
-    matches!(parse(c), Char::SimpleStructural(_))
-}
-
-pub fn is_complex_structural(c: char) -> bool {
-    matches!(parse(c), Char::ComplexStructural(_))
-}
+/// Precomputed classes of ASCII chars
+static ASCII: [Char; 128] = {
+    let mut table = [Char::Regular; 128];
+    let mut i = 0;
+    while i < 128 {
+        table[i] = classify(i as u8 as char);
+        i += 1;
+    }
+    table
+};
 
-pub fn is_structural(c: char) -> bool {
-    is_simple_structural(c) || is_complex_structural(c)
-}
+pub fn parse(c: char) -> Char {
+    if c.is_ascii() {
+        return ASCII[c as usize];
+    }
 
-pub fn is_simple_control(c: char) -> bool {
-    matches!(parse(c), Char::SimpleControl(_))
+    // Only whitespace has a special meaning outside of ASCII,
+    // and there is no whitespace between ASCII and U+1680
+    if c >= '\u{1680}' && whitespace::is_clojure_whitespace(c) {
+        Char::Whitespace
+    } else {
+        Char::Regular
+    }
 }
 
-pub fn is_complex_control(c: char) -> bool {
-    matches!(parse(c), Char::ComplexControl(_))
+/// True for ASCII bytes which can be a part of a symbol/keyword
+pub fn is_word_byte(b: u8) -> bool {
+    b.is_ascii() && matches!(ASCII[b as usize], Char::SimpleControl(_) | Char::ComplexControl(_) | Char::Regular)
 }
 
-pub fn is_control(c: char) -> bool {
-    is_simple_control(c) || is_complex_control(c)
+/// True for ASCII whitespace bytes
+pub fn is_whitespace_byte(b: u8) -> bool {
+    b.is_ascii() && matches!(ASCII[b as usize], Char::Whitespace)
 }
 
 pub fn is_whitespace(c: char) -> bool {
     matches!(parse(c), Char::Whitespace)
 }
 
-pub fn is_regular(c: char) -> bool {
-    matches!(parse(c), Char::Regular) 
-}
-
 pub fn is_word(c: char) -> bool {
-    is_control(c) || is_regular(c)
-}
\ No newline at end of file
+    matches!(parse(c), Char::SimpleControl(_) | Char::ComplexControl(_) | Char::Regular)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, is_whitespace, is_whitespace_byte, is_word, is_word_byte, parse};
+
+    #[test]
+    fn table_matches_classification() {
+        for c in (0..=0x3000u32).filter_map(char::from_u32) {
+            assert_eq!(parse(c), classify(c), "{:?}", c);
+        }
+        for b in 0..=255u8 {
+            let c = b as char;
+            assert_eq!(is_word_byte(b), b.is_ascii() && is_word(c), "{}", b);
+            assert_eq!(is_whitespace_byte(b), b.is_ascii() && is_whitespace(c), "{}", b);
+        }
+    }
+}
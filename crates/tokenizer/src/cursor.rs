@@ -1,36 +1,95 @@
-use std::str::CharIndices;
+use crate::span::Position;
 
 pub struct Cursor<'a> {
     stream: &'a str,
-    iter: CharIndices<'a>,
+    /// Byte offset of the next char
+    next: usize,
+    /// Byte offset of the last read char
     offset: usize,
-    last_char_len: usize
+    last_char_len: usize,
+    /// Position of the last read char
+    before: Position,
+    /// Position right after the last read char
+    after: Position,
 }
 
 impl<'a> Cursor<'a> {
+    /// Cursor over `stream`, which starts at `start` in the source
+    pub fn new(stream: &'a str, start: Position) -> Self {
+        Cursor {
+            stream,
+            next: 0,
+            offset: 0,
+            last_char_len: 0,
+            before: start,
+            after: start,
+        }
+    }
+
     pub fn next(&mut self) -> Option<char> {
-        let (i, c) = self.iter.next()?;
-        self.offset = i;
+        let c = self.peek()?;
+        self.offset = self.next;
         self.last_char_len = c.len_utf8();
+        self.next += self.last_char_len;
+        self.before = self.after;
+        self.after = self.after.advance(c);
         Some(c)
     }
-    
+
+    /// The next char, without advancing the cursor
+    pub fn peek(&self) -> Option<char> {
+        self.stream[self.next..].chars().next()
+    }
+
+    /// Reads the next `len` bytes at once, as if they were read by `next`
+    fn skip(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        let skipped = &self.stream[self.next..self.next + len];
+        let last = (0..len).rfind(|&i| skipped.is_char_boundary(i)).unwrap_or(0);
+
+        self.offset = self.next + last;
+        self.last_char_len = len - last;
+        self.next += len;
+        self.before = self.after.advance_str(&skipped[..last]);
+        self.after = self.before.advance_str(&skipped[last..]);
+    }
+
+    /// Reads ASCII bytes while `f` returns true. Never stops inside a multi-byte char
+    pub fn skip_ascii_while(&mut self, f: impl Fn(u8) -> bool) {
+        let rest = &self.stream.as_bytes()[self.next..];
+        let len = rest.iter().position(|&b| !(b.is_ascii() && f(b))).unwrap_or(rest.len());
+        self.skip(len);
+    }
+
+    /// Reads bytes until an ASCII byte for which `f` returns true, without reading it
+    pub fn skip_until_ascii(&mut self, f: impl Fn(u8) -> bool) {
+        let rest = &self.stream.as_bytes()[self.next..];
+        let len = rest.iter().position(|&b| b.is_ascii() && f(b)).unwrap_or(rest.len());
+        self.skip(len);
+    }
+
     pub fn split_after(&self) -> (&'a str, &'a str) {
         self.stream.split_at(self.offset + self.last_char_len)
     }
-    
+
     pub fn split_before(&self) -> (&'a str, &'a str) {
         self.stream.split_at(self.offset)
     }
+
+    pub fn position_after(&self) -> Position {
+        self.after
+    }
+
+    pub fn position_before(&self) -> Position {
+        self.before
+    }
 }
 
 impl<'a> From<&'a str> for Cursor<'a> {
     fn from(value: &'a str) -> Self {
-        Cursor {
-            stream: value,
-            iter: value.char_indices(),
-            offset: 0,
-            last_char_len: 0,
-        }
+        Cursor::new(value, Position::default())
     }
-}
\ No newline at end of file
+}
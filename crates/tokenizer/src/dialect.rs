@@ -0,0 +1,202 @@
+use std::path::Path;
+
+use crate::error::ErrorKind;
+use crate::token::{Token, TokenType};
+
+/// The grammar accepted by the tokenizer.
+///
+/// Tokens not supported by the dialect are emitted as `ErrorKind::UnsupportedSyntax` errors
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dialect {
+    /// Everything the tokenizer reads, `#js` literals and a shebang included.
+    /// Nothing is rejected, so it suits sources of unknown dialect
+    #[default]
+    Permissive,
+    /// Everything but `#js` literals. The reader reads `#!` as a comment, on the first line too
+    Clojure,
+    /// Clojure plus `#js` literals
+    ClojureScript,
+    /// Data only, after the EDN spec: no quote, syntax quote, unquote, deref, metadata,
+    /// `#(`, `#'`, `#?`, `#::`, regex literals, `#!` and other dispatch macros
+    Edn,
+    /// Clojure with a shebang on the first line
+    Babashka,
+}
+
+impl Dialect {
+    /// Dialect of a file by its extension. `.cljc` files may contain ClojureScript code,
+    /// so they are read as ClojureScript
+    pub fn from_path(path: &Path) -> Option<Dialect> {
+        match path.extension()?.to_str()? {
+            "clj" => Some(Dialect::Clojure),
+            "cljs" | "cljc" => Some(Dialect::ClojureScript),
+            "edn" => Some(Dialect::Edn),
+            "bb" => Some(Dialect::Babashka),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dialect::Permissive => "permissive",
+            Dialect::Clojure => "Clojure",
+            Dialect::ClojureScript => "ClojureScript",
+            Dialect::Edn => "EDN",
+            Dialect::Babashka => "Babashka",
+        }
+    }
+
+    /// True if `#!` on the first line is a shebang rather than a comment
+    pub(crate) fn reads_shebang(&self) -> bool {
+        matches!(self, Dialect::Permissive | Dialect::Babashka)
+    }
+
+    fn accepts(&self, token: &Token) -> bool {
+        use TokenType::*;
+
+        match (self, token.kind()) {
+            (
+                Dialect::Edn,
+                Quote | SynQuote | Unquote | UnquoteSplicing | Deref | Metadata | LAnonFn | Regex | VarQuote
+                | ReaderConditional | ReaderConditionalSplicing | Dispatch,
+            ) => false,
+            // `#!` comments are a reader macro
+            (Dialect::Edn, Comment) => !token.value().starts_with("#!"),
+            // Auto-resolved namespaced maps need a current namespace
+            (Dialect::Edn, NamespacedMap) => !token.value().starts_with("#::"),
+            // EDN tags are user-defined, so only the Clojure readers know there is no `#js`
+            (Dialect::Clojure | Dialect::Babashka, Tag) => token.value() != "#js",
+            _ => true,
+        }
+    }
+
+    /// Turns the token into an error if the dialect doesn't support it
+    pub(crate) fn restrict<'a>(&self, token: Token<'a>) -> Token<'a> {
+        if self.accepts(&token) {
+            return token;
+        }
+
+        let kind = TokenType::Error(ErrorKind::UnsupportedSyntax(*self));
+        Token::with_span(token.value(), kind, token.span())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Dialect;
+    use crate::error::ErrorKind;
+    use crate::token::TokenType;
+    use crate::Tokenizer;
+
+    fn kinds(s: &str, dialect: Dialect) -> Vec<(TokenType, &str)> {
+        Tokenizer::from(s)
+            .with_dialect(dialect)
+            .map(|token| (token.kind(), token.value()))
+            .collect()
+    }
+
+    #[test]
+    fn from_path() {
+        assert_eq!(Dialect::from_path(Path::new("src/a/core.clj")), Some(Dialect::Clojure));
+        assert_eq!(Dialect::from_path(Path::new("core.cljs")), Some(Dialect::ClojureScript));
+        assert_eq!(Dialect::from_path(Path::new("core.cljc")), Some(Dialect::ClojureScript));
+        assert_eq!(Dialect::from_path(Path::new("deps.edn")), Some(Dialect::Edn));
+        assert_eq!(Dialect::from_path(Path::new("bb/script.bb")), Some(Dialect::Babashka));
+        assert_eq!(Dialect::from_path(Path::new("README.md")), None);
+        assert_eq!(Dialect::from_path(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn edn() {
+        use TokenType::*;
+        let unsupported = Error(ErrorKind::UnsupportedSyntax(Dialect::Edn));
+
+        assert_eq!(kinds("`a", Dialect::Edn), vec![(unsupported, "`"), (Symbol, "a")]);
+        assert_eq!(kinds("~@a", Dialect::Edn), vec![(unsupported, "~@"), (Symbol, "a")]);
+        assert_eq!(kinds("@a", Dialect::Edn), vec![(unsupported, "@"), (Symbol, "a")]);
+        assert_eq!(kinds("#(a)", Dialect::Edn), vec![(unsupported, "#("), (Symbol, "a"), (RParen, ")")]);
+        assert_eq!(kinds(r#"#"a""#, Dialect::Edn), vec![(unsupported, r#"#"a""#)]);
+        assert_eq!(kinds("'a", Dialect::Edn)[0], (unsupported, "'"));
+        assert_eq!(kinds("^:m a", Dialect::Edn)[0], (unsupported, "^"));
+        assert_eq!(kinds("#'a", Dialect::Edn)[0], (unsupported, "#'"));
+        assert_eq!(kinds("#?(:clj 1)", Dialect::Edn)[0], (unsupported, "#?"));
+        assert_eq!(kinds("#?@(:clj [1])", Dialect::Edn)[0], (unsupported, "#?@"));
+        assert_eq!(kinds("#=(+ 1 2)", Dialect::Edn)[0], (unsupported, "#"));
+        assert_eq!(kinds("#::{:a 1}", Dialect::Edn)[0], (unsupported, "#::"));
+        assert_eq!(kinds("#inst \"2020\"", Dialect::Edn)[0], (Tag, "#inst"));
+        assert_eq!(kinds("#:a{:b 1}", Dialect::Edn)[0], (NamespacedMap, "#:a"));
+        assert_eq!(kinds("#_a ##Inf", Dialect::Edn)[..4], [(Discard, "#_"), (Symbol, "a"), (Whitespace, " "), (SymbolicValue, "##Inf")]);
+        assert_eq!(kinds("`a", Dialect::Clojure)[0], (SynQuote, "`"));
+    }
+
+    #[test]
+    fn clojure() {
+        use TokenType::*;
+
+        assert_eq!(kinds("#js {}", Dialect::Clojure)[0], (Error(ErrorKind::UnsupportedSyntax(Dialect::Clojure)), "#js"));
+        assert_eq!(kinds("#jsx {}", Dialect::Clojure)[0], (Tag, "#jsx"));
+        assert_eq!(kinds("#?(:clj ^:m #'a)", Dialect::Clojure)[..4], [
+            (ReaderConditional, "#?"), (LParen, "("), (Keyword, ":clj"), (Whitespace, " "),
+        ]);
+    }
+
+    #[test]
+    fn permissive() {
+        use TokenType::*;
+
+        assert_eq!(Dialect::default(), Dialect::Permissive);
+        assert_eq!(kinds("#js {}", Dialect::Permissive)[0], (Tag, "#js"));
+        assert_eq!(kinds("#!/usr/bin/env bb\n`(~a @b #(c) #\"d\" #'e #?(:clj ^:f g))", Dialect::Permissive)
+            .into_iter()
+            .filter(|(kind, _)| matches!(kind, Error(_)))
+            .count(), 0);
+        assert_eq!(kinds("#js {}", Dialect::Permissive), kinds("#js {}", Dialect::ClojureScript));
+    }
+
+    #[test]
+    fn babashka() {
+        use TokenType::*;
+
+        for source in ["#js {}", "`(~a @b #(c) #\"d\")", "a\n#!b", "#::{:a 1}"] {
+            let babashka: Vec<_> = kinds(source, Dialect::Babashka).into_iter().map(|(kind, _)| kind).collect();
+            let clojure: Vec<_> = kinds(source, Dialect::Clojure).into_iter().map(|(kind, _)| match kind {
+                Error(ErrorKind::UnsupportedSyntax(Dialect::Clojure)) => Error(ErrorKind::UnsupportedSyntax(Dialect::Babashka)),
+                kind => kind,
+            }).collect();
+            assert_eq!(babashka, clojure);
+        }
+        assert_eq!(kinds("#js {}", Dialect::Babashka)[0], (Error(ErrorKind::UnsupportedSyntax(Dialect::Babashka)), "#js"));
+
+        // The only difference: the first line `#!` is a shebang, not a comment
+        assert_eq!(kinds("#!/usr/bin/env bb\n(a)", Dialect::Babashka)[..2], [(Shebang, "#!/usr/bin/env bb"), (Whitespace, "\n")]);
+        assert_eq!(kinds("#!/usr/bin/env bb\n(a)", Dialect::Clojure)[..2], [(Comment, "#!/usr/bin/env bb\n"), (LParen, "(")]);
+    }
+
+    #[test]
+    fn clojurescript() {
+        use TokenType::*;
+
+        assert_eq!(kinds("#js {}", Dialect::ClojureScript)[0], (Tag, "#js"));
+        assert_eq!(kinds("(.-prop js/window)", Dialect::ClojureScript), vec![
+            (LParen, "("), (Symbol, ".-prop"), (Whitespace, " "), (Symbol, "js/window"), (RParen, ")"),
+        ]);
+    }
+
+    #[test]
+    fn shebang() {
+        use TokenType::*;
+
+        assert_eq!(kinds("#!/usr/bin/env bb\n(a)", Dialect::Babashka)[..3], [
+            (Shebang, "#!/usr/bin/env bb"), (Whitespace, "\n"), (LParen, "("),
+        ]);
+        assert_eq!(kinds("#!/usr/bin/env bb", Dialect::Permissive), vec![(Shebang, "#!/usr/bin/env bb")]);
+        assert_eq!(kinds("\u{feff}#!/usr/bin/env bb", Dialect::Clojure)[1], (Comment, "#!/usr/bin/env bb"));
+        assert_eq!(kinds("#!/usr/bin/env bb", Dialect::ClojureScript), vec![(Comment, "#!/usr/bin/env bb")]);
+        assert_eq!(kinds("#!x", Dialect::Edn), vec![(Error(ErrorKind::UnsupportedSyntax(Dialect::Edn)), "#!x")]);
+        assert_eq!(kinds("a\n#!x", Dialect::Clojure)[2], (Comment, "#!x"));
+        assert_eq!(kinds("a\n#!x", Dialect::Edn)[2], (Error(ErrorKind::UnsupportedSyntax(Dialect::Edn)), "#!x"));
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+use crate::dialect::Dialect;
+
+/// Reason of a tokenization failure.
+///
+/// The tokenizer never stops on an error: it emits an `Error` token and continues
+/// right after it. Each variant documents which part of the input the error token covers.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorKind {
+    /// A string without the closing `"`. The token covers the rest of the input
+    UnterminatedString,
+    /// A regex without the closing `"`. The token covers the rest of the input
+    UnterminatedRegex,
+    /// A `\` at the end of the input. The token covers the `\`
+    MissingCharacter,
+    /// A character literal which is neither a single char nor a known name or escape.
+    /// The token covers the whole literal
+    InvalidCharacter,
+    /// A `:` not followed by a name. The token covers the `:` only,
+    /// so a following delimiter is still tokenized
+    EmptyKeyword,
+    /// A keyword like `:::a`, `:a/` or `:/`. The token covers the whole word
+    InvalidKeyword,
+    /// A symbol like `a/` or `a::b`. The token covers the whole word
+    InvalidSymbol,
+    /// A malformed number like `1.2.3`. The token covers the whole word
+    InvalidNumber,
+    /// A `##` not followed by `Inf`, `-Inf` or `NaN`. The token covers the whole word
+    InvalidSymbolicValue,
+    /// A `#:` not followed by a namespace. The token covers the `#:`
+    InvalidNamespacedMap,
+    /// A `#` followed by whitespace, a closing delimiter or the end of the input.
+    /// The token covers the `#` only
+    InvalidDispatch,
+    /// A token which is valid Clojure, but not in the dialect, e.g. `@` in EDN.
+    /// The token covers the unsupported token
+    UnsupportedSyntax(Dialect),
+}
+
+impl ErrorKind {
+    /// Human-readable description of the error
+    pub fn message(&self) -> &'static str {
+        match self {
+            ErrorKind::UnterminatedString => "unterminated string",
+            ErrorKind::UnterminatedRegex => "unterminated regex",
+            ErrorKind::MissingCharacter => "expected a character after `\\`",
+            ErrorKind::InvalidCharacter => "invalid character literal",
+            ErrorKind::EmptyKeyword => "expected a keyword name after `:`",
+            ErrorKind::InvalidKeyword => "invalid keyword",
+            ErrorKind::InvalidSymbol => "invalid symbol",
+            ErrorKind::InvalidNumber => "invalid number",
+            ErrorKind::InvalidSymbolicValue => "invalid symbolic value, expected `##Inf`, `##-Inf` or `##NaN`",
+            ErrorKind::InvalidNamespacedMap => "expected a namespace after `#:`",
+            ErrorKind::InvalidDispatch => "expected a dispatch character after `#`",
+            ErrorKind::UnsupportedSyntax(Dialect::Permissive) => "syntax is not supported",
+            ErrorKind::UnsupportedSyntax(Dialect::Clojure) => "syntax is not supported in Clojure",
+            ErrorKind::UnsupportedSyntax(Dialect::ClojureScript) => "syntax is not supported in ClojureScript",
+            ErrorKind::UnsupportedSyntax(Dialect::Edn) => "syntax is not supported in EDN",
+            ErrorKind::UnsupportedSyntax(Dialect::Babashka) => "syntax is not supported in Babashka",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
@@ -0,0 +1,163 @@
+use std::ops::Range;
+
+use crate::dialect::Dialect;
+use crate::span::{Position, Span};
+use crate::token::Token;
+use crate::Tokenizer;
+
+/// A replacement of a byte range of the source
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Edit<'e> {
+    /// Byte range of the old source which is replaced
+    pub range: Range<usize>,
+    pub text: &'e str,
+}
+
+impl<'e> Edit<'e> {
+    pub fn new(range: Range<usize>, text: &'e str) -> Self {
+        Edit { range, text }
+    }
+
+    /// Applies the edit to the old source
+    pub fn apply(&self, source: &str) -> String {
+        let mut result = String::with_capacity(source.len() - self.range.len() + self.text.len());
+        result.push_str(&source[..self.range.start]);
+        result.push_str(self.text);
+        result.push_str(&source[self.range.end..]);
+        result
+    }
+
+    /// End of the inserted text in the new source
+    fn new_end(&self) -> usize {
+        self.range.start + self.text.len()
+    }
+}
+
+/// Moves `pos` located after `old` so that it keeps its place relative to `new`.
+/// Columns only change on the line of `old`
+fn shift(pos: Position, old: Position, new: Position) -> Position {
+    let column = if pos.line == old.line {
+        pos.column - old.column + new.column
+    } else {
+        pos.column
+    };
+    Position::new(pos.offset - old.offset + new.offset, pos.line - old.line + new.line, column)
+}
+
+fn reslice<'a>(source: &'a str, token: &Token<'_>, span: Span) -> Token<'a> {
+    Token::with_span(&source[span.range()], token.kind(), span)
+}
+
+/// Updates `tokens` of the old source after `edit`, `source` being the new source.
+///
+/// Lexing restarts at the token containing or touching the edit start, since a token
+/// looks one char past its end. It stops as soon as a new token starts where an old one
+/// did after the edit: the tokenizer keeps no state between tokens, so the rest is the same
+/// and only gets shifted.
+pub fn retokenize<'a>(tokens: &[Token<'_>], source: &'a str, edit: &Edit) -> Vec<Token<'a>> {
+    retokenize_with_dialect(tokens, source, edit, Dialect::default())
+}
+
+/// Same as `retokenize` for tokens produced in the given dialect
+pub fn retokenize_with_dialect<'a>(tokens: &[Token<'_>], source: &'a str, edit: &Edit, dialect: Dialect) -> Vec<Token<'a>> {
+    let restart = tokens
+        .partition_point(|token| token.start().offset < edit.range.start)
+        .saturating_sub(1);
+
+    let mut result: Vec<_> = tokens[..restart]
+        .iter()
+        .map(|token| reslice(source, token, token.span()))
+        .collect();
+
+    let start = tokens.get(restart).map_or(Position::default(), |token| token.start());
+    let mut old = tokens[restart..]
+        .iter()
+        .skip_while(|token| token.start().offset < edit.range.end)
+        .peekable();
+
+    for token in Tokenizer::resume(source, start).with_dialect(dialect) {
+        let offset = token.start().offset;
+        if offset >= edit.new_end() {
+            // Same offset in the old source
+            let old_offset = offset - edit.new_end() + edit.range.end;
+            while old.next_if(|old| old.start().offset < old_offset).is_some() {}
+
+            // A token at the source start may differ even if the text is the same, e.g. a shebang
+            let same = |old: &&Token| {
+                old.start().offset == old_offset && old.kind() == token.kind() && old.value() == token.value()
+            };
+            if let Some(&sync) = old.next_if(same) {
+                let (from, to) = (sync.start(), token.start());
+                result.push(token);
+                result.extend(old.map(|old| {
+                    let span = Span::new(shift(old.start(), from, to), shift(old.end(), from, to));
+                    reslice(source, old, span)
+                }));
+                return result;
+            }
+        }
+        result.push(token);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{retokenize, Edit};
+    use crate::tokenize;
+
+    fn check(source: &str, edit: Edit) {
+        let new_source = edit.apply(source);
+        let old = tokenize(source);
+        assert_eq!(retokenize(&old, &new_source, &edit), tokenize(&new_source), "{:?} in {:?}", edit, source);
+    }
+
+    #[test]
+    fn matches_full_tokenize() {
+        let source = "(ns a)\n\n(defn f [x]\n  ;; ы comment\n  (str \"a\\\"b\" x :k/w))\n";
+
+        for start in 0..=source.len() {
+            if !source.is_char_boundary(start) {
+                continue;
+            }
+            for text in ["", "x", " ", "\n", "\"", ";", "(", "\\", "#", "ы\n "] {
+                check(source, Edit::new(start..start, text));
+            }
+            for end in start..=source.len() {
+                if source.is_char_boundary(end) {
+                    check(source, Edit::new(start..end, ""));
+                    check(source, Edit::new(start..end, "z\n"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn source_start() {
+        check("x #!a\n(b)", Edit::new(0..2, ""));
+        check("#!a\n(b)", Edit::new(0..0, "x "));
+        check("\u{feff}x #!a\n(b)", Edit::new(3..5, ""));
+        check("\u{feff}#!a\n(b)", Edit::new(0..3, ""));
+    }
+
+    #[test]
+    fn empty_source() {
+        check("", Edit::new(0..0, "(a b)"));
+        check("(a b)", Edit::new(0..5, ""));
+    }
+
+    #[test]
+    fn shifts_positions() {
+        let source = "(a\n  b) c\nd";
+        let edit = Edit::new(1..2, "xy\nz");
+        let new_source = edit.apply(source);
+        let tokens = retokenize(&tokenize(source), &new_source, &edit);
+
+        let spans: Vec<_> = tokens.iter().map(|token| (token.value(), token.start().line, token.start().column)).collect();
+        assert_eq!(spans, vec![
+            ("(", 1, 1), ("xy", 1, 2), ("\n", 1, 4), ("z", 2, 1), ("\n  ", 2, 2),
+            ("b", 3, 3), (")", 3, 4), (" ", 3, 5), ("c", 3, 6), ("\n", 3, 7), ("d", 4, 1),
+        ]);
+    }
+}
@@ -0,0 +1,83 @@
+//! JSON and JSON lines dumps of the token stream, in the serde representation of
+//! `OwnedToken`, e.g. `{"text":"foo","kind":"Symbol","span":{"start":{...},"end":{...}}}`
+//! and `{"kind":{"Error":"UnterminatedString"}}` for errors.
+
+use std::io::{self, Write};
+
+use crate::token::Token;
+
+/// A token as a single line JSON object
+pub fn to_json(token: &Token) -> String {
+    serde_json::to_string(token).expect("tokens serialize to JSON")
+}
+
+/// Writes the tokens as a JSON array, one token per line
+pub fn write_json<'a>(w: &mut impl Write, tokens: impl IntoIterator<Item = Token<'a>>) -> io::Result<()> {
+    w.write_all(b"[")?;
+    for (i, token) in tokens.into_iter().enumerate() {
+        if i > 0 {
+            w.write_all(b",")?;
+        }
+        w.write_all(b"\n")?;
+        w.write_all(to_json(&token).as_bytes())?;
+    }
+    w.write_all(b"\n]\n")
+}
+
+/// Writes the tokens as JSON lines, one object per line
+pub fn write_jsonl<'a>(w: &mut impl Write, tokens: impl IntoIterator<Item = Token<'a>>) -> io::Result<()> {
+    for token in tokens {
+        w.write_all(to_json(&token).as_bytes())?;
+        w.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_json, write_json, write_jsonl};
+    use crate::dialect::Dialect;
+    use crate::token::OwnedToken;
+    use crate::Tokenizer;
+
+    #[test]
+    fn token() {
+        let token = Tokenizer::from("\"a\\\"\tы\"").next().unwrap();
+        assert_eq!(to_json(&token), concat!(
+            r#"{"text":"\"a\\\"\tы\"","kind":"String","#,
+            r#""span":{"start":{"offset":0,"line":1,"column":1},"end":{"offset":8,"line":1,"column":8}}}"#,
+        ));
+
+        let token = Tokenizer::from(":").next().unwrap();
+        assert!(to_json(&token).contains(r#""kind":{"Error":"EmptyKeyword"}"#));
+
+        let token = Tokenizer::from("@a").with_dialect(Dialect::Edn).next().unwrap();
+        assert!(to_json(&token).contains(r#""kind":{"Error":{"UnsupportedSyntax":"Edn"}}"#));
+    }
+
+    #[test]
+    fn formats() {
+        let mut out = vec![];
+        write_jsonl(&mut out, Tokenizer::from("a b")).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().count(), 3);
+        assert!(out.lines().all(|line| line.starts_with(r#"{"text":"#)));
+
+        let mut out = vec![];
+        write_json(&mut out, Tokenizer::from("")).unwrap();
+        assert_eq!(out, b"[\n]\n");
+    }
+
+    #[test]
+    fn owned() {
+        let source = "#!x\n(a :b \"c\\n\" @d) ; e\n: \u{1}\u{8}";
+        for dialect in [Dialect::Clojure, Dialect::Edn] {
+            for token in Tokenizer::from(source).with_dialect(dialect) {
+                let json = to_json(&token);
+                let owned: OwnedToken = serde_json::from_str(&json).unwrap();
+                assert_eq!(owned, OwnedToken::from(token));
+                assert_eq!(serde_json::to_string(&owned).unwrap(), json);
+            }
+        }
+    }
+}
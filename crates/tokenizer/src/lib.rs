@@ -1,20 +1,47 @@
+use std::iter::FusedIterator;
+
 use cursor::Cursor;
 
 mod whitespace;
 mod characters;
 mod cursor;
+pub mod character;
+pub mod dialect;
+pub mod error;
+pub mod incremental;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod number;
+pub mod reverse;
+pub mod span;
+pub mod stream;
+pub mod string;
+pub mod symbol;
 pub mod token;
+pub mod tree;
 
+use dialect::Dialect;
+use error::ErrorKind;
+use span::{Position, Span};
 use token::{Token, TokenType};
 
 pub struct Tokenizer<'a> {
     stream: &'a str,
+    pos: Position,
+    dialect: Dialect,
+    /// The last token was the BOM, so a shebang may follow
+    after_bom: bool,
 }
 
+const BOM: char = '\u{feff}';
+
 impl<'a> From<&'a str> for Tokenizer<'a> {
     fn from(value: &'a str) -> Self {
         Tokenizer {
             stream: value,
+            pos: Position::default(),
+            dialect: Dialect::default(),
+            after_bom: false,
         }
     }
 }
@@ -26,111 +53,205 @@ enum StringState {
 }
 
 impl<'a> Tokenizer<'a> {
+    /// Tokenizer resuming at `start`, which must be a token boundary of `source`
+    pub(crate) fn resume(source: &'a str, start: Position) -> Self {
+        let mut tokenizer = Tokenizer::starting_at(&source[start.offset..], start);
+        tokenizer.after_bom = start.offset == BOM.len_utf8() && source.starts_with(BOM);
+        tokenizer
+    }
+
+    /// Tokenizer over a part of the source, which starts at `start`
+    pub(crate) fn starting_at(stream: &'a str, start: Position) -> Self {
+        Tokenizer {
+            stream,
+            pos: start,
+            dialect: Dialect::default(),
+            after_bom: false,
+        }
+    }
+
+    /// Restricts the accepted syntax, which is `Dialect::Permissive` by default
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Tells that `stream` follows the BOM, which only the caller knows for a part of the source
+    pub(crate) fn set_after_bom(&mut self, after_bom: bool) {
+        self.after_bom = after_bom;
+    }
+
+    /// Position of the next token
+    pub fn position(&self) -> Position {
+        self.pos
+    }
+
+    fn cursor(&self) -> Cursor<'a> {
+        Cursor::new(self.stream, self.pos)
+    }
+
+    fn emit(&mut self, slice: &'a str, rest: &'a str, end: Position, kind: TokenType) -> Token<'a> {
+        let span = Span::new(self.pos, end);
+        self.stream = rest;
+        self.pos = end;
+        Token::with_span(slice, kind, span)
+    }
+
     fn make_token(&mut self, pos: usize, kind: TokenType) -> Token<'a> {
         let (slice, rest) = self.stream.split_at(pos);
-        self.stream = rest;
-        Token::new(slice, kind)
+        let end = self.pos.advance_str(slice);
+        self.emit(slice, rest, end, kind)
     }
     
     fn make_token_after(&mut self, kind: TokenType, cursor: Cursor<'a>) -> Token<'a> {
         let (slice, rest) = cursor.split_after();
-        self.stream = rest;
-        Token::new(slice, kind)
+        self.emit(slice, rest, cursor.position_after(), kind)
     }
     
     fn make_token_before(&mut self, kind: TokenType, cursor: Cursor<'a>) -> Token<'a> {
         let (slice, rest) = cursor.split_before();
-        self.stream = rest;
-        Token::new(slice, kind)
+        self.emit(slice, rest, cursor.position_before(), kind)
     }
     
-    fn make_token_all(&mut self, kind: TokenType) -> Token<'a> {
+    /// Makes a token of the whole remaining stream. The cursor must be exhausted
+    fn make_token_all(&mut self, kind: TokenType, cursor: Cursor<'a>) -> Token<'a> {
         let stream = std::mem::take(&mut self.stream);
-        Token::new(stream, kind)
+        self.emit(stream, "", cursor.position_after(), kind)
     }
     
     fn make_token_ascii(&mut self, kind: TokenType) -> Token<'a> {
         self.make_token(1, kind)
     }
     
-    fn read_word(&mut self, mut cursor: Cursor<'a>, kind: TokenType) -> Token<'a> {
-        while let Some(c) = cursor.next() {
-            if characters::is_structural(c) || characters::is_whitespace(c) {
-                return self.make_token_before(kind, cursor)
-            }
-        }
-        
-        return self.make_token_all(kind)
+    fn read_word(&mut self, cursor: Cursor<'a>, kind: TokenType) -> Token<'a> {
+        self.read_word_with(cursor, |_| kind)
     }
     
-    fn read_word_strict(&mut self, mut cursor: Cursor<'a>, kind: TokenType) -> Token<'a> {
-        let Some(c) = cursor.next() else {
-            return self.make_token_all(TokenType::Error)
-        };
-        
-        if !characters::is_word(c) {
-            return self.make_token_after(TokenType::Error, cursor)
+    /// Reads a word and decides its kind by the whole word
+    fn read_word_with(&mut self, mut cursor: Cursor<'a>, kind: impl FnOnce(&str) -> TokenType) -> Token<'a> {
+        loop {
+            cursor.skip_ascii_while(characters::is_word_byte);
+            match cursor.next() {
+                Some(c) if !characters::is_word(c) => {
+                    let kind = kind(cursor.split_before().0);
+                    return self.make_token_before(kind, cursor)
+                },
+                Some(_) => (),
+                None => break,
+            }
         }
         
-        self.read_word(cursor, kind)
+        let kind = kind(self.stream);
+        self.make_token_all(kind, cursor)
     }
     
     fn read_symbol(&mut self, cursor: Cursor<'a>) -> Token<'a> {
-        self.read_word(cursor, TokenType::Symbol)
+        self.read_word_with(cursor, |word| match symbol::parse_symbol(word) {
+            Ok(_) => TokenType::Symbol,
+            Err(_) => TokenType::Error(ErrorKind::InvalidSymbol),
+        })
+    }
+
+    fn read_number(&mut self, cursor: Cursor<'a>) -> Token<'a> {
+        self.read_word_with(cursor, |word| match number::classify(word) {
+            Ok(_) => TokenType::Number,
+            Err(_) => TokenType::Error(ErrorKind::InvalidNumber),
+        })
     }
 
     fn read_keyword(&mut self, cursor: Cursor<'a>) -> Token<'a> {
-        self.read_word_strict(cursor, TokenType::Keyword)
+        if !cursor.peek().is_some_and(characters::is_word) {
+            return self.make_token_after(TokenType::Error(ErrorKind::EmptyKeyword), cursor)
+        }
+
+        self.read_word_with(cursor, |word| match symbol::parse_keyword(word) {
+            Ok(_) => TokenType::Keyword,
+            Err(_) => TokenType::Error(ErrorKind::InvalidKeyword),
+        })
     }
 
     fn read_character(&mut self, mut cursor: Cursor<'a>) -> Token<'a> {
         let Some(_) = cursor.next() else {
-            return self.make_token_all(TokenType::Error);
+            return self.make_token_all(TokenType::Error(ErrorKind::MissingCharacter), cursor);
         };
 
-        self.read_word(cursor, TokenType::Character)
+        // The first char is always a part of the literal, even if it's a delimiter
+        self.read_word_with(cursor, |word| match character::parse(word) {
+            Ok(_) => TokenType::Character,
+            Err(_) => TokenType::Error(ErrorKind::InvalidCharacter),
+        })
     }
 
     fn read_comment(&mut self, mut cursor: Cursor<'a>) -> Token<'a> {
-        // We read comment until whitespace or EOF
-        while let Some(c) = cursor.next() {
-            if c == '\n' {
-                return self.make_token_after(TokenType::Comment, cursor)
-            }
+        // We read comment until the end of line or EOF
+        cursor.skip_until_ascii(|b| b == b'\n');
+        if cursor.next().is_some() {
+            return self.make_token_after(TokenType::Comment, cursor)
         }
         
-        self.make_token_all(TokenType::Character)
+        self.make_token_all(TokenType::Comment, cursor)
+    }
+
+    fn read_shebang(&mut self, mut cursor: Cursor<'a>) -> Token<'a> {
+        // The line break, `\n` or `\r\n`, is left for the whitespace
+        cursor.skip_until_ascii(|b| b == b'\n' || b == b'\r');
+        if cursor.peek().is_some() {
+            return self.make_token_after(TokenType::Shebang, cursor)
+        }
+
+        self.make_token_all(TokenType::Shebang, cursor)
     }
 
     fn read_unquote(&mut self, mut cursor: Cursor<'a>) -> Token<'a> {
         match cursor.next() {
             Some('@') => self.make_token_after(TokenType::UnquoteSplicing, cursor),
             Some(_) => self.make_token_before(TokenType::Unquote, cursor),
-            None => self.make_token_all(TokenType::Unquote)
+            None => self.make_token_all(TokenType::Unquote, cursor)
         }
     }
 
     fn read_whitespace(&mut self, mut cursor: Cursor<'a>) -> Token<'a> {
-        while let Some(c) = cursor.next() {
-            if !characters::is_whitespace(c) {
-                return self.make_token_before(TokenType::Whitespace, cursor)
+        loop {
+            cursor.skip_ascii_while(characters::is_whitespace_byte);
+            match cursor.next() {
+                Some(c) if !characters::is_whitespace(c) => {
+                    return self.make_token_before(TokenType::Whitespace, cursor)
+                },
+                Some(_) => (),
+                None => break,
             }
         }
         
-        return self.make_token_all(TokenType::Character)
+        self.make_token_all(TokenType::Whitespace, cursor)
     }
 
     
-    fn read_string(&mut self, mut cursor: Cursor<'a>) -> Token<'a> {
+    fn read_string(&mut self, cursor: Cursor<'a>) -> Token<'a> {
+        self.read_quoted(cursor, TokenType::String, ErrorKind::UnterminatedString)
+    }
+
+    /// Reads until the closing `"`, skipping escaped chars
+    fn read_quoted(&mut self, mut cursor: Cursor<'a>, kind: TokenType, error: ErrorKind) -> Token<'a> {
         let mut state = StringState::Normal;
 
-        while let Some(c) = cursor.next() {
+        loop {
+            if let StringState::Normal = state {
+                cursor.skip_until_ascii(|b| b == b'"' || b == b'\\');
+            }
+            let Some(c) = cursor.next() else {
+                break;
+            };
+
             match (state, c) {
                 (StringState::Normal, '\\') => {
                     state = StringState::Escape;
                 },
                 (StringState::Normal, '"') => {
-                    return self.make_token_after(TokenType::String, cursor);
+                    return self.make_token_after(kind, cursor);
                 },
                 (StringState::Normal, _) => (),
                 (StringState::Escape, _) => {
@@ -139,7 +260,56 @@ impl<'a> Tokenizer<'a> {
             };
         }
 
-        self.make_token_all(TokenType::Error)
+        self.make_token_all(TokenType::Error(error), cursor)
+    }
+    
+    fn read_reader_conditional(&mut self, mut cursor: Cursor<'a>) -> Token<'a> {
+        if cursor.peek() == Some('@') {
+            cursor.next();
+            return self.make_token_after(TokenType::ReaderConditionalSplicing, cursor)
+        }
+
+        self.make_token_after(TokenType::ReaderConditional, cursor)
+    }
+
+    fn read_symbolic_value(&mut self, cursor: Cursor<'a>) -> Token<'a> {
+        self.read_word_with(cursor, |word| match word {
+            "##Inf" | "##-Inf" | "##NaN" => TokenType::SymbolicValue,
+            _ => TokenType::Error(ErrorKind::InvalidSymbolicValue),
+        })
+    }
+
+    fn read_namespaced_map(&mut self, cursor: Cursor<'a>) -> Token<'a> {
+        self.read_word_with(cursor, |word| {
+            if word == "#::" || word.len() > "#:".len() {
+                TokenType::NamespacedMap
+            } else {
+                TokenType::Error(ErrorKind::InvalidNamespacedMap)
+            }
+        })
+    }
+
+    fn read_dispatch(&mut self, mut cursor: Cursor<'a>) -> Token<'a> {
+        let Some(c) = cursor.next() else {
+            return self.make_token_all(TokenType::Error(ErrorKind::InvalidDispatch), cursor)
+        };
+
+        match c {
+            '{' => self.make_token_after(TokenType::LSet, cursor),
+            '(' => self.make_token_after(TokenType::LAnonFn, cursor),
+            '"' => self.read_quoted(cursor, TokenType::Regex, ErrorKind::UnterminatedRegex),
+            '\'' => self.make_token_after(TokenType::VarQuote, cursor),
+            '_' => self.make_token_after(TokenType::Discard, cursor),
+            '?' => self.read_reader_conditional(cursor),
+            '#' => self.read_symbolic_value(cursor),
+            ':' => self.read_namespaced_map(cursor),
+            // Past the first line `#!` is a line comment, as in the Clojure reader
+            '!' => self.read_comment(cursor),
+            // Legacy metadata, eval and unreadable dispatch macros
+            '^' | '=' | '<' => self.make_token_before(TokenType::Dispatch, cursor),
+            c if characters::is_word(c) => self.read_word(cursor, TokenType::Tag),
+            _ => self.make_token_before(TokenType::Error(ErrorKind::InvalidDispatch), cursor)
+        }
     }
     
     fn make_simple_control_token(&mut self, c: characters::SimpleControl) -> Token<'a> {
@@ -149,14 +319,29 @@ impl<'a> Tokenizer<'a> {
     fn make_simple_structural_token(&mut self, c: characters::SimpleStructural) -> Token<'a> {
         self.make_token_ascii(TokenType::from(c))
     }
-    
 
-    pub fn next(&mut self) -> Option<Token<'a>> {
-        let mut cursor = cursor::Cursor::from(self.stream);
+    fn read_token(&mut self) -> Option<Token<'a>> {
+        let mut cursor = self.cursor();
         
-        match characters::parse(cursor.next()?) {
+        let c = cursor.next()?;
+
+        let at_start = self.pos.offset == 0 || std::mem::take(&mut self.after_bom);
+        if at_start && c == BOM && self.pos.offset == 0 {
+            self.after_bom = true;
+            return Some(self.make_token_after(TokenType::Bom, cursor));
+        }
+        if at_start && self.dialect.reads_shebang() && self.stream.starts_with("#!") {
+            return Some(self.read_shebang(cursor));
+        }
+        
+        match characters::parse(c) {
             characters::Char::Whitespace => Some(self.read_whitespace(cursor)),
-            characters::Char::SimpleControl(c) => Some(self.make_simple_control_token(c)),
+            characters::Char::SimpleControl(c) => {
+                match c {
+                    characters::SimpleControl::Hash => Some(self.read_dispatch(cursor)),
+                    characters::SimpleControl::Quote => Some(self.make_simple_control_token(c)),
+                }
+            },
             characters::Char::SimpleStructural(c) => Some(self.make_simple_structural_token(c)),
             characters::Char::ComplexControl(c) => {
                 match c {
@@ -171,14 +356,43 @@ impl<'a> Tokenizer<'a> {
                     characters::ComplexStructural::Tilde => Some(self.read_unquote(cursor))
                 }
             },
-            characters::Char::Regular => Some(self.read_symbol(cursor))
+            characters::Char::Regular => {
+                if number::starts_number(c, self.stream[c.len_utf8()..].chars().next()) {
+                    Some(self.read_number(cursor))
+                } else {
+                    Some(self.read_symbol(cursor))
+                }
+            }
         }
     }
 }
 
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.read_token()?;
+        Some(self.dialect.restrict(token))
+    }
+}
+
+impl FusedIterator for Tokenizer<'_> {}
+
+/// Tokenizes the whole source
+pub fn tokenize(s: &str) -> Vec<Token<'_>> {
+    Tokenizer::from(s).collect()
+}
+
+/// Tokenizes the whole source, skipping whitespace and comments
+pub fn tokenize_without_trivia(s: &str) -> Vec<Token<'_>> {
+    Tokenizer::from(s).filter(|token| !token.is_trivia()).collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{Tokenizer, Token, TokenType};
+    use crate::{tokenize, tokenize_without_trivia, Tokenizer, Token, TokenType};
+    use crate::error::ErrorKind;
+    use crate::span::Position;
     
     fn onetok<'a>(s: &'a str) -> Option<Token<'a>> {
         let mut tokenizer: Tokenizer<'a> = Tokenizer::from(s);
@@ -186,25 +400,29 @@ mod tests {
         token
     }
     
-    fn kw(s: &str) -> Option<Token> {
+    fn kw(s: &str) -> Option<Token<'_>> {
         Some(Token::new(s, TokenType::Keyword))
     }
     
-    fn sym(s: &str) -> Option<Token> {
+    fn sym(s: &str) -> Option<Token<'_>> {
         Some(Token::new(s, TokenType::Symbol))
     }
     
-    fn cljstr(s: &str) -> Option<Token> {
+    fn cljstr(s: &str) -> Option<Token<'_>> {
         Some(Token::new(s, TokenType::String))
     }
     
-    fn err(s: &str) -> Option<Token> {
-        Some(Token::new(s, TokenType::Error))
+    fn err(s: &str, error: ErrorKind) -> Option<Token<'_>> {
+        Some(Token::new(s, TokenType::Error(error)))
     }
     
-    fn chr(s: &str) -> Option<Token> {
+    fn chr(s: &str) -> Option<Token<'_>> {
         Some(Token::new(s, TokenType::Character))
     }
+    
+    fn num(s: &str) -> Option<Token<'_>> {
+        Some(Token::new(s, TokenType::Number))
+    }
 
     #[test]
     fn keywords() {
@@ -212,6 +430,8 @@ mod tests {
         assert_eq!(onetok("::abcd :abcd"), kw("::abcd"));
         assert_eq!(onetok(":ab/cd :abcd"), kw(":ab/cd"));
         assert_eq!(onetok(":a.b/cd :abcd"), kw(":a.b/cd"));
+        assert_eq!(onetok(":::a :abcd"), err(":::a", ErrorKind::InvalidKeyword));
+        assert_eq!(onetok(":/ :abcd"), err(":/", ErrorKind::InvalidKeyword));
     }
 
     #[test]
@@ -219,6 +439,77 @@ mod tests {
         assert_eq!(onetok("abcd :abcd"), sym("abcd"));
         assert_eq!(onetok("ab/cd :abcd"), sym("ab/cd"));
         assert_eq!(onetok("a.b/cd :abcd"), sym("a.b/cd"));
+        assert_eq!(onetok("clojure.core// :abcd"), sym("clojure.core//"));
+        assert_eq!(onetok("ab/ :abcd"), err("ab/", ErrorKind::InvalidSymbol));
+    }
+
+    #[test]
+    fn parsed_symbols() {
+        let keyword = onetok("::alias/name").unwrap().keyword().unwrap();
+        assert_eq!((keyword.namespace, keyword.name, keyword.auto_resolved), (Some("alias"), "name", true));
+
+        let symbol = onetok("clojure.core//").unwrap().symbol().unwrap();
+        assert_eq!((symbol.namespace, symbol.name), (Some("clojure.core"), "/"));
+    }
+    
+    #[test]
+    fn numbers() {
+        assert_eq!(onetok("42 :abcd"), num("42"));
+        assert_eq!(onetok("-1.5e3)"), num("-1.5e3"));
+        assert_eq!(onetok("+0xFF]"), num("+0xFF"));
+        assert_eq!(onetok("22/7"), num("22/7"));
+        assert_eq!(onetok("1.2.3"), err("1.2.3", ErrorKind::InvalidNumber));
+        assert_eq!(onetok("-"), sym("-"));
+        assert_eq!(onetok("-a"), sym("-a"));
+        assert_eq!(onetok("+ 1"), sym("+"));
+    }
+    
+    fn kinds(s: &str) -> Vec<(TokenType, &str)> {
+        Tokenizer::from(s).map(|token| (token.kind(), token.value())).collect()
+    }
+
+    #[test]
+    fn dispatch() {
+        use TokenType::*;
+
+        assert_eq!(kinds("#{1}"), vec![(LSet, "#{"), (Number, "1"), (RBrace, "}")]);
+        assert_eq!(kinds("#(f %)"), vec![(LAnonFn, "#("), (Symbol, "f"), (Whitespace, " "), (Symbol, "%"), (RParen, ")")]);
+        assert_eq!(kinds(r#"#"a\"b" x"#), vec![(Regex, r#"#"a\"b""#), (Whitespace, " "), (Symbol, "x")]);
+        assert_eq!(kinds("#'foo"), vec![(VarQuote, "#'"), (Symbol, "foo")]);
+        assert_eq!(kinds("#_ x"), vec![(Discard, "#_"), (Whitespace, " "), (Symbol, "x")]);
+        assert_eq!(kinds("#?(:clj 1)")[..2], [(ReaderConditional, "#?"), (LParen, "(")]);
+        assert_eq!(kinds("#?@(:clj [1])")[..2], [(ReaderConditionalSplicing, "#?@"), (LParen, "(")]);
+        assert_eq!(kinds("##Inf ##-Inf ##NaN"), vec![
+            (SymbolicValue, "##Inf"), (Whitespace, " "),
+            (SymbolicValue, "##-Inf"), (Whitespace, " "),
+            (SymbolicValue, "##NaN"),
+        ]);
+        assert_eq!(kinds("#:ns{:a 1}")[..2], [(NamespacedMap, "#:ns"), (LBrace, "{")]);
+        assert_eq!(kinds("#::{:a 1}")[..2], [(NamespacedMap, "#::"), (LBrace, "{")]);
+        assert_eq!(kinds("#::alias{}")[..2], [(NamespacedMap, "#::alias"), (LBrace, "{")]);
+        assert_eq!(kinds("#inst \"2020\""), vec![(Tag, "#inst"), (Whitespace, " "), (String, "\"2020\"")]);
+        assert_eq!(kinds("#my/tag{}")[..2], [(Tag, "#my/tag"), (LBrace, "{")]);
+        assert_eq!(kinds("#=(+ 1 2)")[..2], [(Dispatch, "#"), (Symbol, "=")]);
+    }
+
+    #[test]
+    fn errors() {
+        use TokenType::*;
+
+        assert_eq!(kinds(r#"#"abc"#), vec![(Error(ErrorKind::UnterminatedRegex), r#"#"abc"#)]);
+        assert_eq!(kinds(":"), vec![(Error(ErrorKind::EmptyKeyword), ":")]);
+        assert_eq!(kinds("(:)"), vec![(LParen, "("), (Error(ErrorKind::EmptyKeyword), ":"), (RParen, ")")]);
+        assert_eq!(kinds(": a"), vec![(Error(ErrorKind::EmptyKeyword), ":"), (Whitespace, " "), (Symbol, "a")]);
+        assert_eq!(kinds("1.2.3)"), vec![(Error(ErrorKind::InvalidNumber), "1.2.3"), (RParen, ")")]);
+        assert_eq!(kinds("##Foo ##"), vec![
+            (Error(ErrorKind::InvalidSymbolicValue), "##Foo"),
+            (Whitespace, " "),
+            (Error(ErrorKind::InvalidSymbolicValue), "##"),
+        ]);
+        assert_eq!(kinds("#:{}")[..2], [(Error(ErrorKind::InvalidNamespacedMap), "#:"), (LBrace, "{")]);
+        assert_eq!(kinds("# a"), vec![(Error(ErrorKind::InvalidDispatch), "#"), (Whitespace, " "), (Symbol, "a")]);
+        assert_eq!(kinds("(#)"), vec![(LParen, "("), (Error(ErrorKind::InvalidDispatch), "#"), (RParen, ")")]);
+        assert_eq!(kinds("#"), vec![(Error(ErrorKind::InvalidDispatch), "#")]);
     }
     
     #[test]
@@ -227,13 +518,95 @@ mod tests {
         assert_eq!(onetok(r#""abc" :abcd"#), cljstr(r#""abc""#));
         assert_eq!(onetok(r#""a\nbc" :abcd"#), cljstr(r#""a\nbc""#));
         assert_eq!(onetok(r#""a\nb\"c" :abcd"#), cljstr(r#""a\nb\"c""#));
-        assert_eq!(onetok(r#""abc"#), err(r#""abc"#));
+        assert_eq!(onetok(r#""abc"#), err(r#""abc"#, ErrorKind::UnterminatedString));
+    }
+    
+    #[test]
+    fn string_values() {
+        let token = Tokenizer::from("(a\n \"b\\q\")")
+            .find(|token| token.kind() == TokenType::String)
+            .unwrap();
+        let errors = token.string().unwrap_err();
+        assert_eq!(errors[0].position, Position::new(6, 2, 4));
+        assert_eq!(onetok(r#""a\tb" :abcd"#).unwrap().string(), Ok("a\tb".to_owned()));
     }
     
     #[test]
     fn characters() {
-        assert_eq!(onetok(r#"\hello" :abcd"#), chr(r#"\hello"#));
+        assert_eq!(onetok(r#"\hello" :abcd"#), err(r#"\hello"#, ErrorKind::InvalidCharacter));
         assert_eq!(onetok(r#"\" :abcd"#), chr(r#"\""#));
-        assert_eq!(onetok(r#"\"#), err(r#"\"#))
+        assert_eq!(onetok(r#"\"#), err(r#"\"#, ErrorKind::MissingCharacter));
+        assert_eq!(onetok(r#"\newline :abcd"#), chr(r#"\newline"#));
+        assert_eq!(onetok(r#"\u0041)"#), chr(r#"\u0041"#));
+        assert_eq!(onetok(r#"\o101]"#), chr(r#"\o101"#));
+        assert_eq!(onetok(r#"\u12 :abcd"#), err(r#"\u12"#, ErrorKind::InvalidCharacter));
+        assert_eq!(onetok(r#"\  :abcd"#), chr(r#"\ "#));
+    }
+
+    #[test]
+    fn delimiter_characters() {
+        use TokenType::*;
+
+        assert_eq!(kinds(r#"(\( \))"#), vec![
+            (LParen, "("), (Character, r#"\("#), (Whitespace, " "), (Character, r#"\)"#), (RParen, ")"),
+        ]);
+        assert_eq!(kinds(r#"[\;]"#), vec![(LBracket, "["), (Character, r#"\;"#), (RBracket, "]")]);
+        assert_eq!(kinds(r#"(\a)"#), vec![(LParen, "("), (Character, r#"\a"#), (RParen, ")")]);
+        assert_eq!(kinds(r#"\(("#), vec![(Character, r#"\("#), (LParen, "(")]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn spans() {
+        let spans: Vec<_> = Tokenizer::from("(a\n  \"ы\nb\") ;c\n:d")
+            .map(|token| (token.value(), token.start(), token.end()))
+            .collect();
+
+        assert_eq!(spans, vec![
+            ("(", Position::new(0, 1, 1), Position::new(1, 1, 2)),
+            ("a", Position::new(1, 1, 2), Position::new(2, 1, 3)),
+            ("\n  ", Position::new(2, 1, 3), Position::new(5, 2, 3)),
+            ("\"ы\nb\"", Position::new(5, 2, 3), Position::new(11, 3, 3)),
+            (")", Position::new(11, 3, 3), Position::new(12, 3, 4)),
+            (" ", Position::new(12, 3, 4), Position::new(13, 3, 5)),
+            (";c\n", Position::new(13, 3, 5), Position::new(16, 4, 1)),
+            (":d", Position::new(16, 4, 1), Position::new(18, 4, 3)),
+        ]);
+    }
+
+    #[test]
+    fn end_of_input() {
+        use TokenType::*;
+
+        assert_eq!(kinds("a  "), vec![(Symbol, "a"), (Whitespace, "  ")]);
+        assert_eq!(kinds("a ;c"), vec![(Symbol, "a"), (Whitespace, " "), (Comment, ";c")]);
+    }
+
+    #[test]
+    fn source_start() {
+        use TokenType::*;
+
+        assert_eq!(kinds("\u{feff}(a)")[..2], [(Bom, "\u{feff}"), (LParen, "(")]);
+        assert_eq!(kinds("\u{feff}#!/usr/bin/env bb\n"), vec![
+            (Bom, "\u{feff}"), (Shebang, "#!/usr/bin/env bb"), (Whitespace, "\n"),
+        ]);
+        assert_eq!(kinds("#!/usr/bin/env bb\r\n(a)")[..2], [(Shebang, "#!/usr/bin/env bb"), (Whitespace, "\r\n")]);
+        assert_eq!(kinds("(a)\n#!b c\n(d)")[3..], [(Whitespace, "\n"), (Comment, "#!b c\n"), (LParen, "("), (Symbol, "d"), (RParen, ")")]);
+        assert_eq!(kinds("(a) #!b"), vec![(LParen, "("), (Symbol, "a"), (RParen, ")"), (Whitespace, " "), (Comment, "#!b")]);
+        assert_eq!(kinds("a\u{feff}"), vec![(Symbol, "a\u{feff}")]);
+        assert_eq!(kinds("\u{3000}#!b")[1..], [(Comment, "#!b")]);
+    }
+
+    #[test]
+    fn iterator() {
+        let mut tokenizer = Tokenizer::from("a");
+        assert!(tokenizer.next().is_some());
+        assert!(tokenizer.next().is_none());
+        assert!(tokenizer.next().is_none());
+
+        let values: Vec<_> = tokenize("(a ;c\n b)").iter().map(|token| token.value()).collect();
+        assert_eq!(values, vec!["(", "a", " ", ";c\n", " ", "b", ")"]);
+
+        let values: Vec<_> = tokenize_without_trivia("(a ;c\n b)").iter().map(|token| token.value()).collect();
+        assert_eq!(values, vec!["(", "a", "b", ")"]);
+    }
+}
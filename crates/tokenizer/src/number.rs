@@ -0,0 +1,320 @@
+use std::fmt;
+
+/// Syntactic classification of a number literal
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum NumberKind {
+    /// Decimal, hexadecimal (`0xFF`) or octal (`017`) integer
+    Long,
+    /// Floating point number, e.g. `-1.5e3`
+    Double,
+    /// Ratio, e.g. `22/7`
+    Ratio,
+    /// Integer with an explicit radix, e.g. `2r1010`
+    Radix,
+    /// Integer with the `N` suffix, e.g. `1N`
+    BigInt,
+    /// Decimal with the `M` suffix, e.g. `3.14M`
+    BigDecimal,
+}
+
+/// Decoded value of a number literal
+#[derive(Debug, PartialEq, Clone)]
+pub enum Number {
+    Long(i64),
+    /// Decimal digits of an integer which doesn't fit into `i64` or has the `N` suffix,
+    /// with a `-` sign if negative and without leading zeros, e.g. `-255` for `-0xFFN`.
+    /// Integers are arbitrary precision, as in Clojure
+    BigInt(String),
+    Double(f64),
+    /// Ratio as written in the source, not reduced, in decimal digits like `BigInt`.
+    /// The sign is on the numerator
+    Ratio { numerator: String, denominator: String },
+    /// Decimal digits without the `M` suffix
+    BigDecimal(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NumberError {
+    /// The text is not a valid number literal, e.g. `1.2.3` or `09`
+    Invalid,
+    /// Ratio with zero denominator
+    DivideByZero,
+}
+
+impl fmt::Display for NumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberError::Invalid => write!(f, "invalid number"),
+            NumberError::DivideByZero => write!(f, "divide by zero"),
+        }
+    }
+}
+
+impl std::error::Error for NumberError {}
+
+fn split_sign(s: &str) -> (bool, &str) {
+    if let Some(rest) = s.strip_prefix('-') {
+        (true, rest)
+    } else {
+        (false, s.strip_prefix('+').unwrap_or(s))
+    }
+}
+
+fn all_digits(s: &str, radix: u32) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_digit(radix))
+}
+
+/// Parses integer body without sign and suffix into (kind, radix, digits)
+fn integer(body: &str) -> Option<(NumberKind, u32, &str)> {
+    if body == "0" {
+        return Some((NumberKind::Long, 10, body));
+    }
+
+    if let Some(digits) = body.strip_prefix("0x").or_else(|| body.strip_prefix("0X")) {
+        return all_digits(digits, 16).then_some((NumberKind::Long, 16, digits));
+    }
+
+    if let Some(digits) = body.strip_prefix('0') {
+        return all_digits(digits, 8).then_some((NumberKind::Long, 8, digits));
+    }
+
+    if let Some((radix, digits)) = body.split_once(['r', 'R']) {
+        if radix.len() > 2 || !all_digits(radix, 10) {
+            return None;
+        }
+        let radix: u32 = radix.parse().ok()?;
+        if !(2..=36).contains(&radix) || !all_digits(digits, radix) {
+            return None;
+        }
+        return Some((NumberKind::Radix, radix, digits));
+    }
+
+    all_digits(body, 10).then_some((NumberKind::Long, 10, body))
+}
+
+/// Checks `digits [. digits*] [e [+-] digits]`
+fn is_float(body: &str) -> bool {
+    let (mantissa, exponent) = match body.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (body, None),
+    };
+
+    let mantissa_ok = match mantissa.split_once('.') {
+        Some((int, frac)) => all_digits(int, 10) && frac.chars().all(|c| c.is_ascii_digit()),
+        None => all_digits(mantissa, 10),
+    };
+
+    let exponent_ok = match exponent {
+        Some(exponent) => all_digits(split_sign(exponent).1, 10),
+        None => true,
+    };
+
+    mantissa_ok && exponent_ok
+}
+
+fn to_i64(negative: bool, digits: &str, radix: u32) -> Option<i64> {
+    let mut value: i64 = 0;
+    for c in digits.chars() {
+        let digit = c.to_digit(radix)? as i64;
+        value = value.checked_mul(radix as i64)
+            .and_then(|v| if negative { v.checked_sub(digit) } else { v.checked_add(digit) })?;
+    }
+    Some(value)
+}
+
+/// Decimal digits of an integer of any size, with a `-` sign if negative
+fn to_decimal(negative: bool, digits: &str, radix: u32) -> String {
+    // Least significant first
+    let mut decimal = vec![0];
+    for c in digits.chars() {
+        let mut carry = c.to_digit(radix).unwrap_or(0);
+        for digit in decimal.iter_mut() {
+            let value = *digit * radix + carry;
+            *digit = value % 10;
+            carry = value / 10;
+        }
+        while carry > 0 {
+            decimal.push(carry % 10);
+            carry /= 10;
+        }
+    }
+    while decimal.len() > 1 && decimal.last() == Some(&0) {
+        decimal.pop();
+    }
+
+    let mut out = String::with_capacity(decimal.len() + 1);
+    if negative && decimal != [0] {
+        out.push('-');
+    }
+    out.extend(decimal.iter().rev().filter_map(|&digit| char::from_digit(digit, 10)));
+    out
+}
+
+fn long_or_bigint(negative: bool, digits: &str, radix: u32) -> Number {
+    match to_i64(negative, digits, radix) {
+        Some(value) => Number::Long(value),
+        None => Number::BigInt(to_decimal(negative, digits, radix)),
+    }
+}
+
+/// Returns true if a token starting with `c` followed by `next` must be read as a number
+pub(crate) fn starts_number(c: char, next: Option<char>) -> bool {
+    match c {
+        '+' | '-' => next.is_some_and(|c| c.is_ascii_digit()),
+        _ => c.is_ascii_digit(),
+    }
+}
+
+/// Classifies the number literal without decoding it
+pub fn classify(s: &str) -> Result<NumberKind, NumberError> {
+    let (_, body) = split_sign(s);
+
+    if let Some((numerator, denominator)) = body.split_once('/') {
+        return (all_digits(numerator, 10) && all_digits(denominator, 10))
+            .then_some(NumberKind::Ratio)
+            .ok_or(NumberError::Invalid);
+    }
+
+    if let Some((kind, _, _)) = integer(body) {
+        return Ok(kind);
+    }
+
+    // `N` is a digit in radix notation, so `36rZZN` is read without a suffix, but `2r1N` with one,
+    // as the Clojure reader does
+    if let Some(int) = body.strip_suffix('N') {
+        if integer(int).is_some() {
+            return Ok(NumberKind::BigInt);
+        }
+    }
+
+    // Digits with a leading zero which are not octal
+    if all_digits(body, 10) {
+        return Err(NumberError::Invalid);
+    }
+
+    if let Some(float) = body.strip_suffix('M') {
+        return is_float(float).then_some(NumberKind::BigDecimal).ok_or(NumberError::Invalid);
+    }
+
+    is_float(body).then_some(NumberKind::Double).ok_or(NumberError::Invalid)
+}
+
+/// Decodes the number literal
+pub fn parse(s: &str) -> Result<Number, NumberError> {
+    let (negative, body) = split_sign(s);
+
+    match classify(s)? {
+        NumberKind::Long | NumberKind::Radix => {
+            let (_, radix, digits) = integer(body).ok_or(NumberError::Invalid)?;
+            Ok(long_or_bigint(negative, digits, radix))
+        },
+        NumberKind::BigInt => {
+            let body = &body[..body.len() - 1];
+            let (_, radix, digits) = integer(body).ok_or(NumberError::Invalid)?;
+            Ok(Number::BigInt(to_decimal(negative, digits, radix)))
+        },
+        NumberKind::Ratio => {
+            let (numerator, denominator) = body.split_once('/').ok_or(NumberError::Invalid)?;
+            let denominator = to_decimal(false, denominator, 10);
+            if denominator == "0" {
+                return Err(NumberError::DivideByZero);
+            }
+            Ok(Number::Ratio { numerator: to_decimal(negative, numerator, 10), denominator })
+        },
+        NumberKind::Double => {
+            s.parse().map(Number::Double).map_err(|_| NumberError::Invalid)
+        },
+        NumberKind::BigDecimal => {
+            let digits = &s[..s.len() - 1];
+            Ok(Number::BigDecimal(digits.strip_prefix('+').unwrap_or(digits).to_owned()))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, parse, Number, NumberError, NumberKind};
+
+    fn bigint(digits: &str) -> Result<Number, NumberError> {
+        Ok(Number::BigInt(digits.to_owned()))
+    }
+
+    fn ratio(numerator: &str, denominator: &str) -> Result<Number, NumberError> {
+        Ok(Number::Ratio { numerator: numerator.to_owned(), denominator: denominator.to_owned() })
+    }
+
+    #[test]
+    fn classification() {
+        assert_eq!(classify("42"), Ok(NumberKind::Long));
+        assert_eq!(classify("-0"), Ok(NumberKind::Long));
+        assert_eq!(classify("0xFF"), Ok(NumberKind::Long));
+        assert_eq!(classify("017"), Ok(NumberKind::Long));
+        assert_eq!(classify("-1.5e3"), Ok(NumberKind::Double));
+        assert_eq!(classify("1."), Ok(NumberKind::Double));
+        assert_eq!(classify("1e-3"), Ok(NumberKind::Double));
+        assert_eq!(classify("2r1010"), Ok(NumberKind::Radix));
+        assert_eq!(classify("36rZZ"), Ok(NumberKind::Radix));
+        assert_eq!(classify("22/7"), Ok(NumberKind::Ratio));
+        assert_eq!(classify("1N"), Ok(NumberKind::BigInt));
+        assert_eq!(classify("0x10N"), Ok(NumberKind::BigInt));
+        assert_eq!(classify("3.14M"), Ok(NumberKind::BigDecimal));
+        assert_eq!(classify("1M"), Ok(NumberKind::BigDecimal));
+        assert_eq!(classify("09.5"), Ok(NumberKind::Double));
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(classify("1.2.3"), Err(NumberError::Invalid));
+        assert_eq!(classify("09"), Err(NumberError::Invalid));
+        assert_eq!(classify("0xG"), Err(NumberError::Invalid));
+        assert_eq!(classify("2r102"), Err(NumberError::Invalid));
+        assert_eq!(classify("37r1"), Err(NumberError::Invalid));
+        assert_eq!(classify("1/-2"), Err(NumberError::Invalid));
+        assert_eq!(classify("1e"), Err(NumberError::Invalid));
+        assert_eq!(classify("1abc"), Err(NumberError::Invalid));
+        assert_eq!(parse("1/0"), Err(NumberError::DivideByZero));
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(parse("42"), Ok(Number::Long(42)));
+        assert_eq!(parse("+42"), Ok(Number::Long(42)));
+        assert_eq!(parse("-0xFF"), Ok(Number::Long(-255)));
+        assert_eq!(parse("017"), Ok(Number::Long(15)));
+        assert_eq!(parse("2r1010"), Ok(Number::Long(10)));
+        assert_eq!(parse("-1.5e3"), Ok(Number::Double(-1500.0)));
+        assert_eq!(parse("1N"), bigint("1"));
+        assert_eq!(parse("9223372036854775808"), bigint("9223372036854775808"));
+        assert_eq!(parse("-9223372036854775808"), Ok(Number::Long(i64::MIN)));
+        assert_eq!(parse("-22/7"), ratio("-22", "7"));
+        assert_eq!(parse("3.14M"), Ok(Number::BigDecimal("3.14".to_owned())));
+    }
+
+    #[test]
+    fn arbitrary_precision() {
+        assert_eq!(parse("1000000000000000000000000000000000000000"), bigint("1000000000000000000000000000000000000000"));
+        assert_eq!(parse("-1000000000000000000000000000000000000000N"), bigint("-1000000000000000000000000000000000000000"));
+        assert_eq!(parse("0x100000000000000000000000000000000N"), bigint("340282366920938463463374607431768211456"));
+        assert_eq!(parse("-0xFFN"), bigint("-255"));
+        assert_eq!(parse("-0N"), bigint("0"));
+        assert_eq!(parse("007N"), bigint("7"));
+        assert_eq!(
+            parse("-100000000000000000000000000000000000000000/0300000000000000000000000000000000000000001"),
+            ratio("-100000000000000000000000000000000000000000", "300000000000000000000000000000000000000001"),
+        );
+        assert_eq!(parse("1/00"), Err(NumberError::DivideByZero));
+    }
+
+    #[test]
+    fn radix_suffix() {
+        // `N` is a digit in radix 24 and up
+        assert_eq!(classify("36rZZN"), Ok(NumberKind::Radix));
+        assert_eq!(parse("36rZZN"), Ok(Number::Long(46655 - 12)));
+        assert_eq!(parse("24rN"), Ok(Number::Long(23)));
+        // Otherwise it is the suffix
+        assert_eq!(classify("2r101N"), Ok(NumberKind::BigInt));
+        assert_eq!(parse("2r101N"), bigint("5"));
+        assert_eq!(parse("16rFFN"), bigint("255"));
+        assert_eq!(classify("2r102N"), Err(NumberError::Invalid));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::dialect::Dialect;
+use crate::span::Position;
+use crate::token::Token;
+use crate::Tokenizer;
+
+/// Tokenizer yielding tokens backwards from a position.
+///
+/// Strings, comments and character literals can't be recognized reading backwards,
+/// so the source is lexed forwards in segments, starting at restart points: lines beginning
+/// with `(` in the first column, as top-level forms do, and the start of the source.
+///
+/// Such a line may be inside a multi-line string, and only lexing from a known-good point
+/// tells, so the restart points are found by lexing from the start of the source to the
+/// position once, keeping only their positions. The segments are lexed again as they are
+/// reached, so only the tokens of one segment are held at a time.
+pub struct ReverseTokenizer<'a> {
+    source: &'a str,
+    dialect: Dialect,
+    /// Tokens of the current segment in the source order
+    tokens: Vec<Token<'a>>,
+    /// Starts of the segments before `end`, found on the first call to `next`
+    restarts: Option<Vec<Position>>,
+    /// Start of the current segment, which is the end of the next one
+    end: Position,
+}
+
+impl<'a> ReverseTokenizer<'a> {
+    /// Yields tokens which start before `end`. The first one may contain `end`.
+    /// `end` must be a valid position in `source`, e.g. the position of the editor cursor
+    pub fn new(source: &'a str, end: Position) -> Self {
+        ReverseTokenizer {
+            source,
+            dialect: Dialect::default(),
+            tokens: Vec::new(),
+            restarts: None,
+            end,
+        }
+    }
+
+    /// Restricts the accepted syntax, which is `Dialect::Permissive` by default
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    fn tokenizer(&self, start: Position) -> Tokenizer<'a> {
+        Tokenizer::resume(self.source, start).with_dialect(self.dialect)
+    }
+
+    /// Restart points before `self.end` in the source order
+    fn restarts(&self) -> Vec<Position> {
+        let end = self.end.offset;
+        let mut restarts = vec![Position::default()];
+        restarts.extend(
+            self.tokenizer(Position::default())
+                .take_while(|token| token.start().offset < end)
+                .filter(|token| token.start().offset > 0 && token.start().column == 1 && token.value() == "(")
+                .map(|token| token.start()),
+        );
+        restarts
+    }
+}
+
+impl<'a> Iterator for ReverseTokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if let Some(token) = self.tokens.pop() {
+            return Some(token);
+        }
+        if self.end.offset == 0 {
+            return None;
+        }
+
+        if self.restarts.is_none() {
+            self.restarts = Some(self.restarts());
+        }
+        let start = self.restarts.as_mut().and_then(Vec::pop).unwrap_or_default();
+        let end = self.end.offset;
+        self.tokens = self.tokenizer(start)
+            .take_while(|token| token.start().offset < end)
+            .collect();
+        self.end = start;
+        self.tokens.pop()
+    }
+}
+
+/// The innermost opening delimiter enclosing `pos`, scanning backwards.
+/// Returns `None` at the top level
+pub fn enclosing_open<'a>(source: &'a str, pos: Position, dialect: Dialect) -> Option<Token<'a>> {
+    let mut depth = 0usize;
+
+    for token in ReverseTokenizer::new(source, pos).with_dialect(dialect) {
+        // A delimiter containing `pos` is only possible as an error token, skip it
+        if token.end().offset > pos.offset {
+            continue;
+        }
+        if token.is_right() {
+            depth += 1;
+        } else if token.is_left() {
+            match depth.checked_sub(1) {
+                Some(outer) => depth = outer,
+                None => return Some(token),
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{enclosing_open, ReverseTokenizer};
+    use crate::dialect::Dialect;
+    use crate::span::LineIndex;
+    use crate::tokenize;
+
+    const SOURCE: &str = "(ns a)\n\n(defn f\n  \"Doc string\n(with a paren\n\" [x]\n  ;; (comment\n  [\\( \\\" x])\n(def s \"\n(\")\n(g)";
+
+    fn matches_forward(source: &str) {
+        let index = LineIndex::new(source);
+        let forward = tokenize(source);
+
+        for offset in 0..=source.len() {
+            let Some(pos) = index.position(offset) else {
+                continue;
+            };
+            let mut expected: Vec<_> = forward.iter().copied().filter(|token| token.start().offset < offset).collect();
+            expected.reverse();
+            let actual: Vec<_> = ReverseTokenizer::new(source, pos).collect();
+            assert_eq!(actual, expected, "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn restart_points() {
+        matches_forward(SOURCE);
+        matches_forward("");
+        matches_forward("\u{feff}(a)\n(b)");
+    }
+
+    #[test]
+    fn strings_with_restart_points() {
+        // Each line of the strings begins with `(`, so lexing from one reaches the next
+        matches_forward("(def s \"\n(a\n(b\n(c \\\" d\n\")\n(e)\n(f #\"\n(g\n(h\")\n(i \"\n(j\n(k");
+    }
+
+    #[test]
+    fn enclosing() {
+        let index = LineIndex::new(SOURCE);
+        let open = |offset| enclosing_open(SOURCE, index.position(offset).unwrap(), Dialect::Clojure)
+            .map(|token| (token.value(), token.start().offset));
+
+        assert_eq!(open(0), None);
+        assert_eq!(open(3), Some(("(", 0)));
+        assert_eq!(open(7), None);
+        let vector = SOURCE.find("[\\(").unwrap();
+        assert_eq!(open(vector + 4), Some(("[", vector)));
+        assert_eq!(open(vector + 9), Some(("(", 8)));
+        assert_eq!(open(vector + 10), None);
+        assert_eq!(open(SOURCE.len() - 1), Some(("(", SOURCE.len() - 3)));
+        assert_eq!(open(SOURCE.len()), None);
+    }
+}
@@ -0,0 +1,135 @@
+use std::ops::Range;
+
+/// A location in the source text
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// Byte offset from the beginning of the source
+    pub offset: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number, counted in characters
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(offset: usize, line: usize, column: usize) -> Self {
+        Position { offset, line, column }
+    }
+
+    /// Position right after `c`, if `c` starts at `self`
+    pub fn advance(self, c: char) -> Self {
+        if c == '\n' {
+            Position::new(self.offset + 1, self.line + 1, 1)
+        } else {
+            Position::new(self.offset + c.len_utf8(), self.line, self.column + 1)
+        }
+    }
+
+    /// Position right after `s`, if `s` starts at `self`
+    pub fn advance_str(self, s: &str) -> Self {
+        // Works on bytes, as only `\n` and char starts matter
+        s.bytes().fold(self, |pos, b| match b {
+            b'\n' => Position::new(pos.offset + 1, pos.line + 1, 1),
+            // UTF-8 continuation byte
+            0x80..=0xBF => Position::new(pos.offset + 1, pos.line, pos.column),
+            _ => Position::new(pos.offset + 1, pos.line, pos.column + 1),
+        })
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::new(0, 1, 1)
+    }
+}
+
+/// A half-open region of the source text
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Byte range of the span, suitable for slicing the source
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+}
+
+/// Maps byte offsets back to lines and columns
+pub struct LineIndex<'a> {
+    source: &'a str,
+    /// Byte offsets of the first character of every line
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        LineIndex { source, line_starts }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Position of the byte `offset`.
+    /// Returns `None` if the offset is out of bounds or is not on a char boundary
+    pub fn position(&self, offset: usize) -> Option<Position> {
+        if !self.source.is_char_boundary(offset) {
+            return None;
+        }
+
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self.source[line_start..offset].chars().count() + 1;
+
+        Some(Position::new(offset, line, column))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineIndex, Position};
+
+    #[test]
+    fn advance() {
+        let pos = Position::default().advance_str("ab\nc");
+        assert_eq!(pos, Position::new(4, 2, 2));
+
+        let pos = Position::default().advance_str("ыы");
+        assert_eq!(pos, Position::new(4, 1, 3));
+    }
+
+    #[test]
+    fn line_index() {
+        let index = LineIndex::new("ab\nыc\n\nd");
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.position(0), Some(Position::new(0, 1, 1)));
+        assert_eq!(index.position(2), Some(Position::new(2, 1, 3)));
+        assert_eq!(index.position(3), Some(Position::new(3, 2, 1)));
+        assert_eq!(index.position(4), None);
+        assert_eq!(index.position(5), Some(Position::new(5, 2, 2)));
+        assert_eq!(index.position(8), Some(Position::new(8, 4, 1)));
+        assert_eq!(index.position(9), Some(Position::new(9, 4, 2)));
+        assert_eq!(index.position(10), None);
+    }
+}
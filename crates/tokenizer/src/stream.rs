@@ -0,0 +1,231 @@
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+use crate::dialect::Dialect;
+use crate::span::Position;
+use crate::token::{OwnedToken, TokenType};
+use crate::Tokenizer;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Tokenizer reading the source from `io::Read`, which doesn't hold the whole source in memory.
+///
+/// Only the text of the last unfinished token is kept between reads. A token ending at the
+/// end of the buffered text may continue in the next read, so it's lexed again once more
+/// text arrives.
+pub struct StreamTokenizer<R> {
+    reader: R,
+    /// Text which is not tokenized yet
+    buffer: String,
+    /// Bytes of an incomplete UTF-8 sequence at the end of the last read
+    pending: Vec<u8>,
+    /// Position of the buffer start
+    pos: Position,
+    /// Tokens lexed from the buffer, which are not yielded yet
+    ready: VecDeque<OwnedToken>,
+    eof: bool,
+    dialect: Dialect,
+    /// The last yielded token is the BOM, so a shebang may follow
+    after_bom: bool,
+}
+
+impl<R: Read> StreamTokenizer<R> {
+    pub fn new(reader: R) -> Self {
+        StreamTokenizer {
+            reader,
+            buffer: String::new(),
+            pending: Vec::new(),
+            pos: Position::default(),
+            ready: VecDeque::new(),
+            eof: false,
+            dialect: Dialect::default(),
+            after_bom: false,
+        }
+    }
+
+    /// Restricts the accepted syntax, which is `Dialect::Permissive` by default
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Reads more text into the buffer
+    fn fill(&mut self) -> io::Result<()> {
+        // Growing reads keep re-lexing of a huge token linear
+        let mut chunk = vec![0; CHUNK_SIZE.max(self.buffer.len())];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+
+        if read == 0 {
+            self.eof = true;
+            if !self.pending.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "incomplete UTF-8 sequence at the end of the stream"));
+            }
+            return Ok(());
+        }
+
+        self.pending.extend_from_slice(&chunk[..read]);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            // The sequence may be completed by the next read
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+
+        let text = std::str::from_utf8(&self.pending[..valid]).expect("validated above");
+        self.buffer.push_str(text);
+        self.pending.drain(..valid);
+        Ok(())
+    }
+
+    /// Moves complete tokens from the buffer to `ready`
+    fn lex(&mut self) {
+        let buffer_end = self.pos.offset + self.buffer.len();
+        let mut end = self.pos;
+
+        let mut tokenizer = Tokenizer::starting_at(&self.buffer, self.pos).with_dialect(self.dialect);
+        tokenizer.set_after_bom(self.after_bom);
+
+        for token in tokenizer {
+            if token.end().offset == buffer_end && !self.eof {
+                break;
+            }
+            end = token.end();
+            self.after_bom = token.kind() == TokenType::Bom;
+            self.ready.push_back(OwnedToken::from(token));
+        }
+
+        self.buffer.drain(..end.offset - self.pos.offset);
+        self.pos = end;
+    }
+}
+
+impl<I> StreamTokenizer<ChunkReader<I>>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    /// Tokenizer over a sequence of chunks, which may split chars and tokens anywhere
+    pub fn from_chunks(chunks: impl IntoIterator<IntoIter = I>) -> Self {
+        StreamTokenizer::new(ChunkReader::new(chunks.into_iter()))
+    }
+}
+
+impl<R: Read> Iterator for StreamTokenizer<R> {
+    type Item = io::Result<OwnedToken>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token) = self.ready.pop_front() {
+                return Some(Ok(token));
+            }
+            if self.eof {
+                return None;
+            }
+            if let Err(e) = self.fill() {
+                self.eof = true;
+                self.buffer.clear();
+                return Some(Err(e));
+            }
+            self.lex();
+        }
+    }
+}
+
+/// `io::Read` over a sequence of byte chunks
+pub struct ChunkReader<I: Iterator> {
+    chunks: I,
+    current: Option<I::Item>,
+    offset: usize,
+}
+
+impl<I: Iterator> ChunkReader<I> {
+    pub fn new(chunks: I) -> Self {
+        ChunkReader { chunks, current: None, offset: 0 }
+    }
+}
+
+impl<I> Read for ChunkReader<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(chunk) = &self.current {
+                let rest = &chunk.as_ref()[self.offset..];
+                if !rest.is_empty() {
+                    let len = rest.len().min(buf.len());
+                    buf[..len].copy_from_slice(&rest[..len]);
+                    self.offset += len;
+                    return Ok(len);
+                }
+            }
+
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.current = Some(chunk);
+                    self.offset = 0;
+                },
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::StreamTokenizer;
+    use crate::token::OwnedToken;
+    use crate::Tokenizer;
+
+    fn expected(source: &str) -> Vec<OwnedToken> {
+        Tokenizer::from(source).map(OwnedToken::from).collect()
+    }
+
+    fn chunked(source: &str, size: usize) -> Vec<OwnedToken> {
+        StreamTokenizer::from_chunks(source.as_bytes().chunks(size))
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn chunk_boundaries() {
+        let source = "\u{feff}#!/usr/bin/env bb\n(ns a)\n;; комментарий\n(def s \"ы \\\" 😀\")\n#?@(:clj [\\λ -1 ::k/w]) #\"re\" :";
+
+        for size in 1..=source.len() {
+            assert_eq!(chunked(source, size), expected(source), "chunk size {}", size);
+        }
+    }
+
+    #[test]
+    fn reader() {
+        let source = "[1 2 3] \"unterminated";
+        let tokens: Vec<_> = StreamTokenizer::new(source.as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(tokens, expected(source));
+        assert_eq!(tokens[8].start().offset, 8);
+    }
+
+    #[test]
+    fn large_token() {
+        let source = format!("\"{}\" x", "a".repeat(100_000));
+        assert_eq!(chunked(&source, 1000), expected(&source));
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let result: io::Result<Vec<_>> = StreamTokenizer::from_chunks([&b"a \xff"[..]]).collect();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        let result: io::Result<Vec<_>> = StreamTokenizer::from_chunks([&b"a \xd1"[..]]).collect();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+use std::str::Chars;
+
+use crate::span::Position;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StringErrorKind {
+    /// Unknown escape like `\q`
+    UnsupportedEscape(char),
+    /// Malformed `\uXXXX` escape
+    InvalidUnicode,
+    /// Octal escape above `\377`
+    InvalidOctal,
+    /// Missing opening or closing `"`
+    Unterminated,
+}
+
+/// An error inside a string literal
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StringError {
+    pub kind: StringErrorKind,
+    /// Position of the `\` starting the escape, or of the literal for `Unterminated`
+    pub position: Position,
+}
+
+impl fmt::Display for StringErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StringErrorKind::UnsupportedEscape(c) => write!(f, "unsupported escape character: \\{}", c),
+            StringErrorKind::InvalidUnicode => write!(f, "invalid unicode escape, expected \\uXXXX"),
+            StringErrorKind::InvalidOctal => write!(f, "invalid octal escape, expected \\0 to \\377"),
+            StringErrorKind::Unterminated => write!(f, "unterminated string"),
+        }
+    }
+}
+
+impl fmt::Display for StringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.position.line, self.position.column, self.kind)
+    }
+}
+
+impl std::error::Error for StringError {}
+
+fn hex4(chars: &mut Chars) -> Option<u32> {
+    let digits = chars.as_str().get(..4)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    chars.nth(3);
+    u32::from_str_radix(digits, 16).ok()
+}
+
+fn unicode(chars: &mut Chars) -> Result<char, StringErrorKind> {
+    let code = hex4(chars).ok_or(StringErrorKind::InvalidUnicode)?;
+
+    // Strings are UTF-16 on the JVM, so non-BMP chars are written as surrogate pairs
+    if (0xD800..0xDC00).contains(&code) {
+        let mut lookahead = chars.clone();
+        if lookahead.as_str().starts_with("\\u") {
+            lookahead.nth(1);
+            if let Some(low @ 0xDC00..=0xDFFF) = hex4(&mut lookahead) {
+                *chars = lookahead;
+                let code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                return char::from_u32(code).ok_or(StringErrorKind::InvalidUnicode);
+            }
+        }
+    }
+
+    // Lone surrogates can't be represented in a Rust string
+    char::from_u32(code).ok_or(StringErrorKind::InvalidUnicode)
+}
+
+fn octal(first: char, chars: &mut Chars) -> Result<char, StringErrorKind> {
+    let mut code = first.to_digit(8).ok_or(StringErrorKind::InvalidOctal)?;
+
+    for _ in 0..2 {
+        let Some(digit) = chars.clone().next().and_then(|c| c.to_digit(8)) else {
+            break;
+        };
+        chars.next();
+        code = code * 8 + digit;
+    }
+
+    if code > 0o377 {
+        return Err(StringErrorKind::InvalidOctal);
+    }
+    char::from_u32(code).ok_or(StringErrorKind::InvalidOctal)
+}
+
+fn escape(c: char, chars: &mut Chars) -> Result<char, StringErrorKind> {
+    match c {
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        'n' => Ok('\n'),
+        '\\' => Ok('\\'),
+        '"' => Ok('"'),
+        'b' => Ok('\u{0008}'),
+        'f' => Ok('\u{000c}'),
+        'u' => unicode(chars),
+        '0'..='7' => octal(c, chars),
+        _ => Err(StringErrorKind::UnsupportedEscape(c)),
+    }
+}
+
+/// Decodes a string literal including the quotes, which starts at `start`.
+/// Reports every invalid escape
+pub fn decode(s: &str, start: Position) -> Result<String, Vec<StringError>> {
+    let body = s.strip_prefix('"')
+        .and_then(|body| body.strip_suffix('"'))
+        // A lone `"` is both prefix and suffix
+        .filter(|_| s.len() >= 2);
+
+    let Some(body) = body else {
+        return Err(vec![StringError { kind: StringErrorKind::Unterminated, position: start }]);
+    };
+
+    let mut result = String::with_capacity(body.len());
+    let mut errors = vec![];
+    let mut chars = body.chars();
+    let mut position = start.advance('"');
+
+    while let Some(c) = chars.next() {
+        let rest = chars.as_str();
+
+        if c == '\\' {
+            let decoded = match chars.next() {
+                Some(c) => escape(c, &mut chars),
+                // Can't happen in a token, the closing quote would be escaped
+                None => Err(StringErrorKind::Unterminated),
+            };
+            match decoded {
+                Ok(c) => result.push(c),
+                Err(kind) => errors.push(StringError { kind, position }),
+            }
+        } else {
+            result.push(c);
+        }
+
+        let consumed = &rest[..rest.len() - chars.as_str().len()];
+        position = position.advance(c).advance_str(consumed);
+    }
+
+    if errors.is_empty() {
+        Ok(result)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, StringError, StringErrorKind};
+    use crate::span::Position;
+
+    fn dec(s: &str) -> Result<String, Vec<StringError>> {
+        decode(s, Position::default())
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(dec(r#""abc""#), Ok("abc".to_owned()));
+        assert_eq!(dec(r#""""#), Ok("".to_owned()));
+        assert_eq!(dec(r#""a\nb\tc\rd""#), Ok("a\nb\tc\rd".to_owned()));
+        assert_eq!(dec(r#""\"\\\b\f""#), Ok("\"\\\u{8}\u{c}".to_owned()));
+        assert_eq!(dec(r#""Aλы""#), Ok("Aλы".to_owned()));
+        assert_eq!(dec(r#""\101\0\12x\3777""#), Ok("A\0\nx\u{ff}7".to_owned()));
+        assert_eq!(dec("\"multi\nline\""), Ok("multi\nline".to_owned()));
+        assert_eq!(dec(r#""\u0041\uD83D\uDE00""#), Ok("A😀".to_owned()));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(dec(r#""a\qb""#), Err(vec![StringError {
+            kind: StringErrorKind::UnsupportedEscape('q'),
+            position: Position::new(2, 1, 3),
+        }]));
+        assert_eq!(dec("\"ы\n\\u12\\400\""), Err(vec![
+            StringError { kind: StringErrorKind::InvalidUnicode, position: Position::new(4, 2, 1) },
+            StringError { kind: StringErrorKind::InvalidOctal, position: Position::new(8, 2, 5) },
+        ]));
+        assert_eq!(dec(r#""\uDE00""#).map_err(|e| e[0].kind), Err(StringErrorKind::InvalidUnicode));
+        assert_eq!(dec(r#""\8""#), Err(vec![StringError {
+            kind: StringErrorKind::UnsupportedEscape('8'),
+            position: Position::new(1, 1, 2),
+        }]));
+        assert_eq!(dec(r#""abc"#), Err(vec![StringError {
+            kind: StringErrorKind::Unterminated,
+            position: Position::default(),
+        }]));
+        assert_eq!(dec(r#"""#).map_err(|e| e[0].kind), Err(StringErrorKind::Unterminated));
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+
+/// A symbol split into namespace and name
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Symbol<'a> {
+    pub namespace: Option<&'a str>,
+    pub name: &'a str,
+}
+
+/// A keyword split into namespace and name, without the leading colons
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Keyword<'a> {
+    /// For auto-resolved keywords this is the alias, e.g. `alias` for `::alias/name`
+    pub namespace: Option<&'a str>,
+    pub name: &'a str,
+    /// True for `::name` and `::alias/name`
+    pub auto_resolved: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SymbolError {
+    /// The text doesn't follow the symbol rules, e.g. `a/` or `:::a`
+    Invalid,
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolError::Invalid => write!(f, "invalid symbol"),
+        }
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+impl fmt::Display for Symbol<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.namespace {
+            Some(namespace) => write!(f, "{}/{}", namespace, self.name),
+            None => f.write_str(self.name),
+        }
+    }
+}
+
+impl fmt::Display for Keyword<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let colons = if self.auto_resolved { "::" } else { ":" };
+        match self.namespace {
+            Some(namespace) => write!(f, "{}{}/{}", colons, namespace, self.name),
+            None => write!(f, "{}{}", colons, self.name),
+        }
+    }
+}
+
+fn starts_with_digit(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Splits `ns/name` following the Clojure reader rules.
+/// `name_may_start_with_digit` is only allowed for unqualified keywords
+fn split(s: &str, name_may_start_with_digit: bool) -> Result<Symbol<'_>, SymbolError> {
+    if s == "/" {
+        return Ok(Symbol { namespace: None, name: s });
+    }
+
+    let (namespace, name) = if let Some(namespace) = s.strip_suffix("//") {
+        (Some(namespace), "/")
+    } else {
+        match s.rsplit_once('/') {
+            Some((namespace, name)) => (Some(namespace), name),
+            None => (None, s),
+        }
+    };
+
+    let valid_name = name == "/"
+        || (!name.is_empty()
+            && !name.ends_with(':')
+            && (name_may_start_with_digit && namespace.is_none() || !starts_with_digit(name)));
+
+    let valid_namespace = match namespace {
+        Some(namespace) => !namespace.is_empty()
+            && !namespace.starts_with('/')
+            && !namespace.ends_with(':')
+            && !starts_with_digit(namespace),
+        None => true,
+    };
+
+    if !valid_name || !valid_namespace || s.contains("::") {
+        return Err(SymbolError::Invalid);
+    }
+
+    Ok(Symbol { namespace, name })
+}
+
+/// Parses a symbol like `name`, `ns/name`, `/` or `clojure.core//`
+pub fn parse_symbol(s: &str) -> Result<Symbol<'_>, SymbolError> {
+    if s.is_empty() || s.starts_with(':') {
+        return Err(SymbolError::Invalid);
+    }
+    split(s, false)
+}
+
+/// Parses a keyword like `:name`, `:ns/name`, `::name` or `::alias/name`
+pub fn parse_keyword(s: &str) -> Result<Keyword<'_>, SymbolError> {
+    let body = s.strip_prefix(':').ok_or(SymbolError::Invalid)?;
+    let (body, auto_resolved) = match body.strip_prefix(':') {
+        Some(body) => (body, true),
+        None => (body, false),
+    };
+
+    if body.is_empty() || body.starts_with(':') || body == "/" {
+        return Err(SymbolError::Invalid);
+    }
+
+    let Symbol { namespace, name } = split(body, true)?;
+    Ok(Keyword { namespace, name, auto_resolved })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_keyword, parse_symbol, Keyword, Symbol, SymbolError};
+
+    fn sym<'a>(namespace: Option<&'a str>, name: &'a str) -> Result<Symbol<'a>, SymbolError> {
+        Ok(Symbol { namespace, name })
+    }
+
+    fn kw<'a>(namespace: Option<&'a str>, name: &'a str, auto_resolved: bool) -> Result<Keyword<'a>, SymbolError> {
+        Ok(Keyword { namespace, name, auto_resolved })
+    }
+
+    #[test]
+    fn symbols() {
+        assert_eq!(parse_symbol("abc"), sym(None, "abc"));
+        assert_eq!(parse_symbol("a.b/cd"), sym(Some("a.b"), "cd"));
+        assert_eq!(parse_symbol("/"), sym(None, "/"));
+        assert_eq!(parse_symbol("clojure.core//"), sym(Some("clojure.core"), "/"));
+        assert_eq!(parse_symbol("js/console.log"), sym(Some("js"), "console.log"));
+        assert_eq!(parse_symbol("a/b/c"), sym(Some("a/b"), "c"));
+        assert_eq!(parse_symbol("foo#"), sym(None, "foo#"));
+        assert_eq!(parse_symbol(".-prop"), sym(None, ".-prop"));
+    }
+
+    #[test]
+    fn invalid_symbols() {
+        assert_eq!(parse_symbol("a/"), Err(SymbolError::Invalid));
+        assert_eq!(parse_symbol("/a"), Err(SymbolError::Invalid));
+        assert_eq!(parse_symbol("a:"), Err(SymbolError::Invalid));
+        assert_eq!(parse_symbol("a::b"), Err(SymbolError::Invalid));
+        assert_eq!(parse_symbol("a:/b"), Err(SymbolError::Invalid));
+        assert_eq!(parse_symbol("a/1"), Err(SymbolError::Invalid));
+        assert_eq!(parse_symbol(":a"), Err(SymbolError::Invalid));
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(parse_keyword(":abc"), kw(None, "abc", false));
+        assert_eq!(parse_keyword(":ns/name"), kw(Some("ns"), "name", false));
+        assert_eq!(parse_keyword("::name"), kw(None, "name", true));
+        assert_eq!(parse_keyword("::alias/name"), kw(Some("alias"), "name", true));
+        assert_eq!(parse_keyword(":1"), kw(None, "1", false));
+        assert_eq!(parse_keyword(":clojure.core//"), kw(Some("clojure.core"), "/", false));
+    }
+
+    #[test]
+    fn invalid_keywords() {
+        assert_eq!(parse_keyword(":::a"), Err(SymbolError::Invalid));
+        assert_eq!(parse_keyword(":a/"), Err(SymbolError::Invalid));
+        assert_eq!(parse_keyword(":/"), Err(SymbolError::Invalid));
+        assert_eq!(parse_keyword(":"), Err(SymbolError::Invalid));
+        assert_eq!(parse_keyword("::"), Err(SymbolError::Invalid));
+        assert_eq!(parse_keyword(":a::b"), Err(SymbolError::Invalid));
+        assert_eq!(parse_keyword(":a:"), Err(SymbolError::Invalid));
+        assert_eq!(parse_keyword(":a/1"), Err(SymbolError::Invalid));
+        assert_eq!(parse_keyword("a"), Err(SymbolError::Invalid));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(parse_keyword("::alias/name").unwrap().to_string(), "::alias/name");
+        assert_eq!(parse_symbol("clojure.core//").unwrap().to_string(), "clojure.core//");
+    }
+}
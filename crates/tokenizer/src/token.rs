@@ -1,18 +1,47 @@
 use crate::characters;
+use crate::character::{self, CharacterError};
+use crate::error::ErrorKind;
+use crate::number::{self, Number, NumberError};
+use crate::span::{Position, Span};
+use crate::string::{self, StringError};
+use crate::symbol::{self, Keyword, Symbol, SymbolError};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenType {
-    Error,
+    Error(ErrorKind),
     LBrace,
     RBrace,
     LBracket,
     RBracket,
     LParen,
     RParen,
+    /// The `#{` set literal opening
+    LSet,
+    /// The `#(` anonymous function literal opening
+    LAnonFn,
     Metadata,
+    /// The `#` followed by an unsupported dispatch character
     Dispatch,
+    /// The `#"..."` regex literal
+    Regex,
+    /// The `#'` var quote
+    VarQuote,
+    /// The `#_` discard
+    Discard,
+    /// The `#?` reader conditional
+    ReaderConditional,
+    /// The `#?@` splicing reader conditional
+    ReaderConditionalSplicing,
+    /// The `##Inf`, `##-Inf` and `##NaN` symbolic values
+    SymbolicValue,
+    /// The `#:ns`, `#::` or `#::alias` namespaced map prefix
+    NamespacedMap,
+    /// The tag of a tagged literal, e.g. `#inst`
+    Tag,
     Quote,
     Deref,
+    /// A `;` or `#!` line comment with its line break
     Comment,
     Character,
     SynQuote,
@@ -20,19 +49,33 @@ pub enum TokenType {
     UnquoteSplicing,
     Keyword,
     Symbol,
+    Number,
     Whitespace,
-    String
+    String,
+    /// The `#!` line at the beginning of the source, without the line break
+    Shebang,
+    /// The byte order mark at the beginning of the source
+    Bom,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Token<'a> {
+    #[cfg_attr(feature = "serde", serde(rename = "text"))]
     data: &'a str,
     kind: TokenType,
+    span: Span,
 }
 
 impl<'a> Token<'a> {
+    /// Token located at the very beginning of the source
     pub fn new(data: &'a str, kind: TokenType) -> Token<'a> {
-        Token { data, kind }
+        let start = Position::default();
+        Token::with_span(data, kind, Span::new(start, start.advance_str(data)))
+    }
+
+    pub fn with_span(data: &'a str, kind: TokenType, span: Span) -> Token<'a> {
+        Token { data, kind, span }
     }
     
     pub fn kind(&self) -> TokenType {
@@ -42,10 +85,60 @@ impl<'a> Token<'a> {
     pub fn value(&self) -> &'a str {
         self.data
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn start(&self) -> Position {
+        self.span.start
+    }
+
+    pub fn end(&self) -> Position {
+        self.span.end
+    }
+    
+    /// The reason of the failure if it's an error token
+    pub fn error(&self) -> Option<ErrorKind> {
+        match self.kind {
+            TokenType::Error(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Decodes the value of a `Character` token
+    pub fn character(&self) -> Result<char, CharacterError> {
+        character::parse(self.data)
+    }
+
+    /// Decodes the value of a `String` token, reporting invalid escapes
+    pub fn string(&self) -> Result<String, Vec<StringError>> {
+        string::decode(self.data, self.span.start)
+    }
+
+    /// Splits a `Symbol` token into namespace and name
+    pub fn symbol(&self) -> Result<Symbol<'a>, SymbolError> {
+        symbol::parse_symbol(self.data)
+    }
+
+    /// Splits a `Keyword` token into namespace and name
+    pub fn keyword(&self) -> Result<Keyword<'a>, SymbolError> {
+        symbol::parse_keyword(self.data)
+    }
+
+    /// Decodes the value of a `Number` token
+    pub fn number(&self) -> Result<Number, NumberError> {
+        number::parse(self.data)
+    }
+    
+    /// Whitespace, comments, shebang and BOM, which don't affect the meaning of the code
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind(), TokenType::Whitespace | TokenType::Comment | TokenType::Shebang | TokenType::Bom)
+    }
     
     pub fn is_left(&self) -> bool {
         use TokenType::*;
-        matches!(self.kind(), LBrace | LParen | LBracket)
+        matches!(self.kind(), LBrace | LParen | LBracket | LSet | LAnonFn)
     }
 
     pub fn is_right(&self) -> bool {
@@ -54,6 +147,53 @@ impl<'a> Token<'a> {
     }
 }
 
+/// A token which owns its text, so it can outlive the source
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedToken {
+    #[cfg_attr(feature = "serde", serde(rename = "text"))]
+    data: String,
+    kind: TokenType,
+    span: Span,
+}
+
+impl OwnedToken {
+    pub fn kind(&self) -> TokenType {
+        self.kind
+    }
+
+    pub fn value(&self) -> &str {
+        &self.data
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn start(&self) -> Position {
+        self.span.start
+    }
+
+    pub fn end(&self) -> Position {
+        self.span.end
+    }
+
+    /// Borrowed view of the token, which gives access to the value decoders
+    pub fn as_token(&self) -> Token<'_> {
+        Token::with_span(&self.data, self.kind, self.span)
+    }
+}
+
+impl From<Token<'_>> for OwnedToken {
+    fn from(value: Token<'_>) -> Self {
+        OwnedToken {
+            data: value.data.to_owned(),
+            kind: value.kind,
+            span: value.span,
+        }
+    }
+}
+
 impl From<characters::SimpleControl> for TokenType {
     fn from(value: characters::SimpleControl) -> Self {
         match value {
@@ -0,0 +1,296 @@
+use std::fmt;
+
+use crate::span::{Position, Span};
+use crate::token::{Token, TokenType};
+
+/// Tokens grouped by the delimiters.
+///
+/// Building never fails: delimiter problems become error nodes, and the tokens
+/// of the source are all kept in the tree, in the source order.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TokenTree<'a> {
+    /// Any token except delimiters
+    Leaf(Token<'a>),
+    Delimited {
+        open: Token<'a>,
+        close: Token<'a>,
+        children: Vec<TokenTree<'a>>,
+    },
+    /// An opening delimiter which is not closed before the end of the source
+    Unclosed {
+        open: Token<'a>,
+        children: Vec<TokenTree<'a>>,
+    },
+    /// Closed by a delimiter of another kind, e.g. `(a]`
+    Mismatched {
+        open: Token<'a>,
+        close: Token<'a>,
+        children: Vec<TokenTree<'a>>,
+    },
+    /// A closing delimiter without an opening one
+    Unmatched(Token<'a>),
+}
+
+/// The closing delimiter kind of an opening delimiter
+fn closing(open: TokenType) -> TokenType {
+    match open {
+        TokenType::LParen | TokenType::LAnonFn => TokenType::RParen,
+        TokenType::LBracket => TokenType::RBracket,
+        TokenType::LBrace | TokenType::LSet => TokenType::RBrace,
+        _ => unreachable!("{:?} is not an opening delimiter", open),
+    }
+}
+
+impl<'a> TokenTree<'a> {
+    /// Groups the tokens into trees.
+    ///
+    /// A closing delimiter always closes the innermost open one, even if it's of another kind.
+    /// That's the way the Clojure reader fails too, so errors point where the reader stops
+    pub fn build(tokens: impl IntoIterator<Item = Token<'a>>) -> Vec<TokenTree<'a>> {
+        // Open delimiters with the children read so far
+        let mut stack: Vec<(Token<'a>, Vec<TokenTree<'a>>)> = Vec::new();
+        let mut roots = Vec::new();
+
+        for token in tokens {
+            let tree = if token.is_left() {
+                stack.push((token, Vec::new()));
+                continue;
+            } else if token.is_right() {
+                match stack.pop() {
+                    Some((open, children)) if closing(open.kind()) == token.kind() => {
+                        TokenTree::Delimited { open, close: token, children }
+                    },
+                    Some((open, children)) => TokenTree::Mismatched { open, close: token, children },
+                    None => TokenTree::Unmatched(token),
+                }
+            } else {
+                TokenTree::Leaf(token)
+            };
+
+            match stack.last_mut() {
+                Some((_, children)) => children.push(tree),
+                None => roots.push(tree),
+            }
+        }
+
+        while let Some((open, children)) = stack.pop() {
+            let tree = TokenTree::Unclosed { open, children };
+            match stack.last_mut() {
+                Some((_, children)) => children.push(tree),
+                None => roots.push(tree),
+            }
+        }
+
+        roots
+    }
+
+    pub fn children(&self) -> &[TokenTree<'a>] {
+        match self {
+            TokenTree::Delimited { children, .. }
+            | TokenTree::Unclosed { children, .. }
+            | TokenTree::Mismatched { children, .. } => children,
+            TokenTree::Leaf(_) | TokenTree::Unmatched(_) => &[],
+        }
+    }
+
+    /// The first token of the tree
+    pub fn first(&self) -> Token<'a> {
+        match self {
+            TokenTree::Leaf(token) | TokenTree::Unmatched(token) => *token,
+            TokenTree::Delimited { open, .. }
+            | TokenTree::Unclosed { open, .. }
+            | TokenTree::Mismatched { open, .. } => *open,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        let end = match self {
+            TokenTree::Delimited { close, .. } | TokenTree::Mismatched { close, .. } => close.end(),
+            TokenTree::Unclosed { open, children } => children.last().map_or(open.end(), |child| child.span().end),
+            TokenTree::Leaf(token) | TokenTree::Unmatched(token) => token.end(),
+        };
+        Span::new(self.first().start(), end)
+    }
+
+    /// True if the tree itself is a delimiter error, not counting the children
+    pub fn is_error(&self) -> bool {
+        matches!(self, TokenTree::Unclosed { .. } | TokenTree::Mismatched { .. } | TokenTree::Unmatched(_))
+    }
+}
+
+/// A delimiter problem, as reported by `errors`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DelimiterError<'a> {
+    Unclosed(Token<'a>),
+    Mismatched { open: Token<'a>, close: Token<'a> },
+    Unmatched(Token<'a>),
+}
+
+impl<'a> DelimiterError<'a> {
+    /// Where the error is reported: the opening delimiter if it's not closed,
+    /// and the closing one otherwise
+    pub fn position(&self) -> Position {
+        match self {
+            DelimiterError::Unclosed(open) => open.start(),
+            DelimiterError::Mismatched { close, .. } | DelimiterError::Unmatched(close) => close.start(),
+        }
+    }
+}
+
+impl fmt::Display for DelimiterError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DelimiterError::Unclosed(open) => write!(f, "unclosed `{}`", open.value()),
+            DelimiterError::Mismatched { open, close } => write!(
+                f,
+                "mismatched `{}`, `{}` at {}:{} is closed by it",
+                close.value(),
+                open.value(),
+                open.start().line,
+                open.start().column
+            ),
+            DelimiterError::Unmatched(close) => write!(f, "unmatched `{}`", close.value()),
+        }
+    }
+}
+
+/// Delimiter errors of the trees, ordered by `position`
+pub fn errors<'a>(trees: &[TokenTree<'a>]) -> Vec<DelimiterError<'a>> {
+    fn collect<'a>(trees: &[TokenTree<'a>], errors: &mut Vec<DelimiterError<'a>>) {
+        for tree in trees {
+            match tree {
+                TokenTree::Unclosed { open, .. } => errors.push(DelimiterError::Unclosed(*open)),
+                TokenTree::Unmatched(close) => errors.push(DelimiterError::Unmatched(*close)),
+                _ => (),
+            }
+            collect(tree.children(), errors);
+            if let TokenTree::Mismatched { open, close, .. } = tree {
+                errors.push(DelimiterError::Mismatched { open: *open, close: *close });
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+    collect(trees, &mut errors);
+    errors
+}
+
+/// The delimiter matching the one containing `offset`, e.g. the `)` for the `(`.
+/// `None` if there is no delimiter at `offset` or it's unmatched or unclosed
+pub fn matching<'a>(trees: &[TokenTree<'a>], offset: usize) -> Option<Token<'a>> {
+    let contains = |token: &Token| token.start().offset <= offset && offset < token.end().offset;
+
+    // Trees are ordered and don't overlap, so only one of them may contain `offset`
+    let index = trees.partition_point(|tree| tree.span().end.offset <= offset);
+    let tree = trees.get(index)?;
+
+    match tree {
+        TokenTree::Delimited { open, close, .. } | TokenTree::Mismatched { open, close, .. } => {
+            if contains(open) {
+                Some(*close)
+            } else if contains(close) {
+                Some(*open)
+            } else {
+                matching(tree.children(), offset)
+            }
+        },
+        TokenTree::Unclosed { .. } => matching(tree.children(), offset),
+        TokenTree::Leaf(_) | TokenTree::Unmatched(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{errors, matching, TokenTree};
+    use crate::tokenize;
+
+    /// The tree written back in a compact form, with error nodes marked
+    fn show(trees: &[TokenTree]) -> String {
+        let words: Vec<_> = trees
+            .iter()
+            .filter(|tree| !tree.first().is_trivia())
+            .map(|tree| match tree {
+                TokenTree::Leaf(token) => token.value().to_string(),
+                TokenTree::Delimited { open, close, children } => {
+                    format!("{}{}{}", open.value(), show(children), close.value())
+                },
+                TokenTree::Unclosed { open, children } => format!("{}{}!unclosed", open.value(), show(children)),
+                TokenTree::Mismatched { open, close, children } => {
+                    format!("{}{}{}!mismatched", open.value(), show(children), close.value())
+                },
+                TokenTree::Unmatched(token) => format!("{}!unmatched", token.value()),
+            })
+            .collect();
+        words.join(" ")
+    }
+
+    fn build(source: &str) -> Vec<TokenTree<'_>> {
+        TokenTree::build(tokenize(source))
+    }
+
+    #[test]
+    fn nesting() {
+        assert_eq!(show(&build("(a [b #{c}] #(d %) {:e \")\"})")), "(a [b #{c}] #(d %) {:e \")\"})");
+        assert_eq!(show(&build("(a (b")), "(a (b!unclosed!unclosed");
+        assert_eq!(show(&build("(a] b)")), "(a]!mismatched b )!unmatched");
+        assert_eq!(show(&build("#{a)")), "#{a)!mismatched");
+        assert_eq!(show(&build(") (a)")), ")!unmatched (a)");
+        assert!(build("").is_empty());
+    }
+
+    #[test]
+    fn keeps_tokens() {
+        fn flatten<'a>(trees: &[TokenTree<'a>], out: &mut Vec<crate::token::Token<'a>>) {
+            for tree in trees {
+                out.push(tree.first());
+                flatten(tree.children(), out);
+                if let TokenTree::Delimited { close, .. } | TokenTree::Mismatched { close, .. } = tree {
+                    out.push(*close);
+                }
+            }
+        }
+
+        let source = "(ns a)\n;; c\n(defn f [x] (g x]\n) [";
+        let mut tokens = vec![];
+        flatten(&build(source), &mut tokens);
+        assert_eq!(tokens, tokenize(source));
+    }
+
+    #[test]
+    fn spans() {
+        let trees = build("(a b) (c d");
+        assert_eq!(trees[0].span().range(), 0..5);
+        assert_eq!(trees[2].span().range(), 6..10);
+        assert_eq!(build("(")[0].span().range(), 0..1);
+    }
+
+    #[test]
+    fn error_list() {
+        let trees = build("(a]\n) {b\n");
+        let errors: Vec<_> = errors(&trees).iter().map(|e| (e.position().offset, e.to_string())).collect();
+        assert_eq!(errors, vec![
+            (2, "mismatched `]`, `(` at 1:1 is closed by it".to_string()),
+            (4, "unmatched `)`".to_string()),
+            (6, "unclosed `{`".to_string()),
+        ]);
+        assert!(super::errors(&build("(a [b] {c d})")).is_empty());
+    }
+
+    #[test]
+    fn matching_delimiter() {
+        let source = "(a [b #{c}] (d)) #(e";
+        let trees = build(source);
+        let at = |offset| matching(&trees, offset).map(|token| token.start().offset);
+
+        assert_eq!(at(0), Some(15));
+        assert_eq!(at(15), Some(0));
+        assert_eq!(at(3), Some(10));
+        assert_eq!(at(6), Some(9));
+        assert_eq!(at(7), Some(9));
+        assert_eq!(at(12), Some(14));
+        assert_eq!(at(1), None);
+        assert_eq!(at(17), None);
+        assert_eq!(at(source.len()), None);
+        assert_eq!(matching(&build("(a]"), 0).map(|token| token.value()), Some("]"));
+    }
+}
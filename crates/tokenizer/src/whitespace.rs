@@ -1,8 +1,8 @@
-fn is_line_separator(c: char) -> bool {
+const fn is_line_separator(c: char) -> bool {
     c == '\u{2028}' // Line Separator
 }
 
-fn is_space_separator(c: char) -> bool {
+const fn is_space_separator(c: char) -> bool {
     match c {
         '\u{0020}' => true, // Space (SP)
         '\u{00A0}' => true, // No-Break Space (NBSP)
@@ -25,7 +25,7 @@ fn is_space_separator(c: char) -> bool {
     }
 }
 
-fn is_paragraph_separator(c: char) -> bool {
+const fn is_paragraph_separator(c: char) -> bool {
     c == '\u{2029}' // Paragraph Separator
 }
 
@@ -43,7 +43,7 @@ fn is_paragraph_separator(c: char) -> bool {
 /// It is '\u001D', U+001D GROUP SEPARATOR.
 /// It is '\u001E', U+001E RECORD SEPARATOR.
 /// It is '\u001F', U+001F UNIT SEPARATOR.
-fn is_java_whitespace(c: char) -> bool {
+const fn is_java_whitespace(c: char) -> bool {
     if c == '\u{00a0}' || c == '\u{2007}' || c == '\u{202f}' {
         return false;
     }
@@ -62,6 +62,6 @@ fn is_java_whitespace(c: char) -> bool {
         || c == '\u{001f}'
 }
 
-pub fn is_clojure_whitespace(c: char) -> bool {
+pub const fn is_clojure_whitespace(c: char) -> bool {
     is_java_whitespace(c) || c == ','
 }